@@ -0,0 +1,207 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+use oc_sort::{BBox, Detection, MatchingStrategy, OCSort, OCSortConfig};
+
+const GRID_SIZE: usize = 20;
+
+/// Counts the heap allocations like the allocator of tests/allocations.rs.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Returns the average number of heap allocations of the update over the frames.
+fn allocations_per_frame(frame_count: usize, mut update: impl FnMut(usize)) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    (0..frame_count).for_each(&mut update);
+    (ALLOCATIONS.load(Ordering::Relaxed) - before) / frame_count
+}
+
+/// Creates a dense frame of GRID_SIZE x GRID_SIZE detections shifted by the frame index.
+fn dense_frame(frame_index: usize) -> Vec<Detection> {
+    grid_frame(frame_index, GRID_SIZE * GRID_SIZE)
+}
+
+/// Creates a frame of object_count detections on a grid shifted by the frame index.
+fn grid_frame(frame_index: usize, object_count: usize) -> Vec<Detection> {
+    let shift = frame_index as f64 * 0.5;
+    (0..object_count)
+        .map(|i| {
+            let x = (i % GRID_SIZE) as f64 * 12.0 + shift;
+            let y = (i / GRID_SIZE) as f64 * 12.0;
+            Detection::new(BBox::new(x, y, x + 10.0, y + 10.0), (i % 3) as u32).with_score(0.9)
+        })
+        .collect()
+}
+
+fn bench_matching_strategies(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dense_update");
+
+    for (name, matching_strategy) in [
+        ("hungarian", MatchingStrategy::Hungarian),
+        ("greedy", MatchingStrategy::Greedy),
+    ] {
+        let mut oc_sort_tracker = OCSort::from_config(OCSortConfig {
+            matching_strategy,
+            ..Default::default()
+        });
+        for frame_index in 0..3 {
+            oc_sort_tracker.update(&dense_frame(frame_index));
+        }
+        let state = oc_sort_tracker.save_state();
+        let frame = dense_frame(3);
+
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || OCSort::load_state(state.clone()),
+                |mut oc_sort_tracker| oc_sort_tracker.update(&frame),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_object_count(c: &mut Criterion) {
+    let mut group = c.benchmark_group("update_object_count");
+
+    for object_count in [50, 100, 200, 400] {
+        let mut oc_sort_tracker = OCSort::from_config(OCSortConfig::default());
+        for frame_index in 0..3 {
+            oc_sort_tracker.update(&grid_frame(frame_index, object_count));
+        }
+        let state = oc_sort_tracker.save_state();
+        let frame = grid_frame(3, object_count);
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(object_count),
+            &frame,
+            |b, frame| {
+                b.iter_batched(
+                    || OCSort::load_state(state.clone()),
+                    |mut oc_sort_tracker| oc_sort_tracker.update(frame),
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+    }
+
+    group.finish();
+}
+
+/// Reports the number of IoU evaluations of each association stage per frame and times
+/// the update. The primary and BYTE stages associate disjoint detection sets and OCR uses
+/// the last observations instead of the predicted boxes, so no IoU pair is computed twice.
+fn bench_iou_evaluations(c: &mut Criterion) {
+    let mut group = c.benchmark_group("iou_evaluations");
+
+    for object_count in [100, 400] {
+        let mut oc_sort_tracker = OCSort::from_config(OCSortConfig::default());
+        for frame_index in 0..3 {
+            oc_sort_tracker.update(&grid_frame(frame_index, object_count));
+        }
+        let state = oc_sort_tracker.save_state();
+        // Low score detections go to the BYTE stage and jumping objects to OCR.
+        let mut frame = grid_frame(3, object_count);
+        for (i, detection) in frame.iter_mut().enumerate() {
+            if i % 4 == 0 {
+                detection.score = 0.4;
+            } else if i % 10 == 5 {
+                detection.bbox.y_1 += 1000.0;
+                detection.bbox.y_2 += 1000.0;
+            }
+        }
+
+        let (_, frame_debug) = OCSort::load_state(state.clone()).update_with_debug(&frame);
+        let evaluations: Vec<String> = frame_debug
+            .stages
+            .iter()
+            .map(|stage| {
+                let count = stage.detection_indices.len() * stage.tracker_indices.len();
+                format!("{:?} {count}", stage.stage)
+            })
+            .collect();
+        println!(
+            "iou_evaluations/{object_count}: {} (detections x trackers: {})",
+            evaluations.join(", "),
+            frame.len() * state.trackers.len()
+        );
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(object_count),
+            &frame,
+            |b, frame| {
+                b.iter_batched(
+                    || OCSort::load_state(state.clone()),
+                    |mut oc_sort_tracker| oc_sort_tracker.update_with_debug(frame),
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn bench_update_iter(c: &mut Criterion) {
+    let mut group = c.benchmark_group("streaming_update");
+    let frame = dense_frame(0);
+
+    // A long running tracker fed the same frame. Streaming sources have to collect a Vec
+    // per frame for update, while update_iter collects into a buffer reused across frames.
+    // The allocations per frame are counted after a few frames to reach the steady state.
+    let mut oc_sort_tracker = OCSort::from_config(OCSortConfig::default());
+    let mut collect_and_update = || {
+        let detections = frame.to_vec();
+        oc_sort_tracker.update(&detections)
+    };
+    allocations_per_frame(5, |_| drop(collect_and_update()));
+    println!(
+        "streaming_update/collect_and_update: {} allocations per frame",
+        allocations_per_frame(20, |_| drop(collect_and_update()))
+    );
+    group.bench_function("collect_and_update", |b| b.iter(&mut collect_and_update));
+
+    let mut oc_sort_tracker = OCSort::from_config(OCSortConfig::default());
+    let mut update_iter = || oc_sort_tracker.update_iter(frame.iter().cloned());
+    allocations_per_frame(5, |_| drop(update_iter()));
+    println!(
+        "streaming_update/update_iter: {} allocations per frame",
+        allocations_per_frame(20, |_| drop(update_iter()))
+    );
+    group.bench_function("update_iter", |b| b.iter(&mut update_iter));
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_matching_strategies,
+    bench_object_count,
+    bench_iou_evaluations,
+    bench_update_iter
+);
+criterion_main!(benches);
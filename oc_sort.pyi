@@ -1,8 +1,57 @@
+from typing import ClassVar
+
+import numpy as np
+import numpy.typing as npt
+
 class BBox:
     """Class representing the properties of a bounding box."""
     def __new__(cls, x_1: float, y_1: float, x_2: float, y_2: float) -> BBox:
         """Construct a new BBox out of the top left (x_1, y_1) and bottom right (x_2, y_2) coordinates."""
 
+    @classmethod
+    def from_cxcywh(cls, cx: float, cy: float, w: float, h: float) -> BBox:
+        """Construct a new BBox out of the center (cx, cy), width and height."""
+
+    def to_cxcywh(self) -> tuple[float, float, float, float]:
+        """Return the (center x, center y, width, height) representation of the bbox."""
+
+    @classmethod
+    def from_xywh(cls, x: float, y: float, w: float, h: float) -> BBox:
+        """Construct a new BBox out of the top left (x, y) coordinates, width and height."""
+
+    def to_xywh(self) -> tuple[float, float, float, float]:
+        """Return the (top left x, top left y, width, height) representation of the bbox."""
+
+    @classmethod
+    def from_normalized(
+        cls, x_1: float, y_1: float, x_2: float, y_2: float, img_w: float, img_h: float
+    ) -> BBox:
+        """Construct a new BBox in pixels out of coordinates normalized to [0, 1] relative to the image size.
+
+        Returns the zero bbox if img_w or img_h is not positive.
+        """
+
+    def to_normalized(self, img_w: float, img_h: float) -> BBox:
+        """Return the bbox with coordinates normalized relative to the image size.
+
+        Returns the zero bbox if img_w or img_h is not positive.
+        """
+
+    def clamp(self, width: float, height: float) -> BBox:
+        """Return the bbox clipped inside the frame [0, width] x [0, height]."""
+
+    def scale(self, sx: float, sy: float) -> BBox:
+        """Return the bbox with the x coordinates multiplied by sx and the y coordinates by sy."""
+
+    def translate(self, dx: float, dy: float) -> BBox:
+        """Return the bbox moved by dx along the x axis and dy along the y axis."""
+
+    def affine(self, sx: float, sy: float, dx: float, dy: float) -> BBox:
+        """Return the bbox with the coordinates mapped with x * sx + dx and y * sy + dy.
+
+        Negative scales flip the bbox instead of making it invalid.
+        """
+
     @property
     def x_1(self) -> float:
         """The min x coordinate of the bbox."""
@@ -19,10 +68,42 @@ class BBox:
     def y_2(self) -> float:
         """The max y coordinate of the bbox."""
 
+    @property
+    def center(self) -> tuple[float, float]:
+        """The (x, y) coordinates of the center of the bbox."""
+
+    @property
+    def width(self) -> float:
+        """The width of the bbox."""
+
+    @property
+    def height(self) -> float:
+        """The height of the bbox."""
+
+    def __eq__(self, other: object) -> bool:
+        """Return True if the coordinates of the bboxes are exactly equal."""
+
+    def __ne__(self, other: object) -> bool: ...
+    def __hash__(self) -> int: ...
+
 class Detection:
     """Class representing the properties of a valid object detection."""
-    def __new__(cls, bbox: BBox, class_id: int, score: float) -> Detection:
-        """Construct a new detection out of the given bbox and class_id."""
+    def __new__(
+        cls,
+        bbox: BBox,
+        class_id: int,
+        score: float,
+        ext_id: int | None = None,
+        embedding: list[float] | None = None,
+        uncertainty: tuple[float, float, float, float] | None = None,
+    ) -> Detection:
+        """Construct a new detection out of the given bbox and class_id.
+
+        ext_id is an optional id assigned to the object by an external model, e.g. a re-ID model.
+        embedding is an optional appearance feature vector of the object.
+        uncertainty is an optional per-coordinate uncertainty of the box as
+        (x, y, area, aspect ratio) factors that scale the measurement noise.
+        """
 
     @property
     def bbox(self) -> BBox:
@@ -33,9 +114,27 @@ class Detection:
         """The class id of the detection."""
 
     @property
-    def score(self) -> int:
+    def score(self) -> float:
         """The confidence score of the detection."""
 
+    @property
+    def ext_id(self) -> int | None:
+        """The external id of the detection."""
+
+    @property
+    def embedding(self) -> list[float] | None:
+        """The appearance feature vector of the detection."""
+
+    @property
+    def uncertainty(self) -> tuple[float, float, float, float] | None:
+        """The per-coordinate uncertainty of the detection."""
+
+    def __eq__(self, other: object) -> bool:
+        """Return True if all the fields are exactly equal."""
+
+    def __ne__(self, other: object) -> bool: ...
+    __hash__: ClassVar[None]  # type: ignore[assignment]
+
 class Track:
     """Class representing a tracked object."""
 
@@ -51,6 +150,43 @@ class Track:
     def class_id(self) -> int:
         """The class id of the tracked object."""
 
+    @property
+    def age(self) -> int:
+        """The age of the tracked object in frames."""
+
+    @property
+    def time_since_update(self) -> int:
+        """The number of frames since the object was last matched to a detection."""
+
+    @property
+    def score(self) -> float:
+        """The score of the last matched detection, decayed for each missed frame."""
+
+    @property
+    def uncertainty(self) -> tuple[float, float]:
+        """The standard deviations of the estimated center x and center y of the object."""
+
+    @property
+    def last_update_frame(self) -> int:
+        """The index of the update in which the object was last matched to a detection."""
+
+    @property
+    def hit_streak(self) -> int:
+        """The number of consecutive frames the object has been matched to a detection."""
+
+    @property
+    def confirmed(self) -> bool:
+        """Whether the hit streak of the object has reached min_hit_streak at least once."""
+
+    def class_name(self, names: dict[int, str]) -> str | None:
+        """Return the name of the class of the object in names, if it has one."""
+
+    def __eq__(self, other: object) -> bool:
+        """Return True if all the fields are exactly equal."""
+
+    def __ne__(self, other: object) -> bool: ...
+    __hash__: ClassVar[None]  # type: ignore[assignment]
+
 class OCSort:
     """The oc sort object tracker."""
 
@@ -75,6 +211,41 @@ class OCSort:
     def get_trackers(self) -> list[Track]:
         """Return currently tracked objects."""
 
+    def active_ids(self) -> list[int]:
+        """Return the ids of the objects returned by get_trackers."""
+
+    def count_by_class(self) -> dict[int, int]:
+        """Return the number of objects returned by get_trackers per class id."""
+
+    def set_class_names(self, names: dict[int, str]) -> None:
+        """Set the names of the class ids. They are kept by reset."""
+
+    def class_name(self, class_id: int) -> str | None:
+        """Return the name of the class id set with set_class_names, if it has one."""
+
+    def reset(self) -> None:
+        """Remove all tracked objects while keeping the configuration."""
+
+    def take_removed_ids(self) -> list[int]:
+        """Return the ids of the tracked objects removed since the last call.
+
+        Each id is returned exactly once.
+        """
+
+    def remove_track(self, id: int) -> bool:
+        """Stop tracking the object with the given id. Return False if no such object is tracked.
+
+        The id is reported by take_removed_ids and is never reused by a new track.
+        """
+
+    def merge_tracks(self, keep_id: int, remove_id: int) -> bool:
+        """Merge the object with id remove_id into the object with id keep_id.
+
+        The observation history of the removed track is added to the kept track, which keeps
+        its own state estimate. Return False if either object is not tracked or the ids are equal.
+        The removed id is reported by take_removed_ids.
+        """
+
     def update(self, detections: list[Detection]) -> list[Track]:
         """Advance the state of the object tracker.
 
@@ -82,3 +253,26 @@ class OCSort:
 
         Note: if no detections are made pass an empty list to advance the internal state.
         """
+
+    def update_array(
+        self,
+        boxes: npt.NDArray[np.float64],
+        classes: npt.NDArray[np.int64],
+        scores: npt.NDArray[np.float64],
+    ) -> npt.NDArray[np.float64]:
+        """Advance the state of the object tracker with detections given as arrays.
+
+        ## Args:
+            - boxes: (N, 4) float64 array of (x_1, y_1, x_2, y_2) bboxes.
+            - classes: length N int64 array of class ids.
+            - scores: length N float64 array of scores.
+
+        Returns a C-contiguous (M, 7) float64 array of the tracked objects with the columns
+        (id, x_1, y_1, x_2, y_2, class_id, score). Returns a (0, 7) array when there are
+        no tracked objects to report.
+
+        Raises ValueError if the shapes of the arrays do not match.
+        """
+
+def iou_batch(bboxes_1: list[BBox], bboxes_2: list[BBox]) -> list[list[float]]:
+    """Return the IoU of every pair of bboxes. Row i holds the IoUs of bboxes_1[i] with each of bboxes_2."""
@@ -1,12 +1,254 @@
-use std::{collections::HashSet, f64::consts::PI};
+//! The association stages of OC-SORT for building custom tracker loops.
+//!
+//! Each stage takes the full detection and tracker slices together with the index sets
+//! of the detections and trackers available for association, and returns
+//! `(matches, unmatched_detections, unmatched_trackers)`:
+//!  - The indices are positions in the given detection and tracker slices, and the
+//!    matches are (detection index, tracker index) pairs.
+//!  - Every given detection index is returned exactly once, either in a match or in
+//!    unmatched_detections, and likewise for the tracker indices.
+//!  - The unmatched indices keep the order of the given index sets, so they can be passed
+//!    on to the next stage as is.
+//!
+//! OCSort::update runs the primary stage with the high score detections, BYTE with the
+//! low score detections and the remaining trackers, and OCR with the remaining
+//! detections and trackers.
+//!
+//! build_cost_matrix returns the cost matrix of the primary stage without solving the
+//! assignment.
 
-use crate::{bbox::BBox, kalman_box_tracker::KalmanBoxTracker, oc_sort_tracker::Detection};
-use pathfinding::prelude::{Matrix, kuhn_munkres_min};
+use std::{
+    f64::consts::PI,
+    ops::{Index, IndexMut},
+};
 
-// used to convert small float to some large integer since
-// the weight matrix of the hungarian algorithm only
-// accepts integers.
-const IOU_MULTIPLIER: f64 = 10000.0;
+use crate::{
+    bbox::BBox, config::OCSortConfig, kalman_box_tracker::KalmanBoxTracker, mask::mask_iou,
+    oc_sort_tracker::Detection,
+};
+use itertools::Itertools;
+use nalgebra::SVector;
+use pathfinding::matrix::Matrix;
+use pathfinding::prelude::kuhn_munkres_min;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+// The largest magnitude of a cost matrix cell, also used as the effectively infinite
+// cost of invalid pairs. Leaves headroom so that the Hungarian solver's potentials
+// cannot overflow.
+const MAX_COST: i64 = i64::MAX / 16;
+
+// The number of matrix cells from which the cost matrices are filled
+// in parallel when the rayon feature is enabled.
+#[cfg(feature = "rayon")]
+const PARALLEL_THRESHOLD: usize = 4096;
+
+/// The bounding box similarity used in association.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AssociationMetric {
+    /// Intersection over union.
+    #[default]
+    Iou,
+    /// Generalized intersection over union.
+    Giou,
+    /// Distance intersection over union.
+    Diou,
+    /// Complete intersection over union.
+    Ciou,
+}
+
+/// The algorithm used to solve the assignment between detections and trackers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MatchingStrategy {
+    /// Optimal assignment with the Hungarian algorithm. O(n³). Replaced by the custom
+    /// Matcher set with OCSort::set_matcher.
+    #[default]
+    Hungarian,
+    /// Assigns the lowest cost pairs first. Faster for very large frames but not optimal.
+    /// Does not use the Matcher set with OCSort::set_matcher.
+    Greedy,
+}
+
+/// A dense matrix of association costs with a row per detection and a column per tracker.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CostMatrix(Matrix<i64>);
+
+impl CostMatrix {
+    /// Creates a matrix of the given size with every cell set to value.
+    pub fn new(rows: usize, columns: usize, value: i64) -> Self {
+        Self(Matrix::new(rows, columns, value))
+    }
+
+    /// Creates a matrix from its rows. Returns None if the rows differ in length.
+    pub fn from_rows(rows: Vec<Vec<i64>>) -> Option<Self> {
+        Matrix::from_rows(rows).ok().map(Self)
+    }
+
+    /// Returns the number of rows, i.e. detections.
+    pub fn rows(&self) -> usize {
+        self.0.rows
+    }
+
+    /// Returns the number of columns, i.e. trackers.
+    pub fn columns(&self) -> usize {
+        self.0.columns
+    }
+
+    /// Returns the cells of the given row.
+    ///
+    /// ## Panics
+    /// Panics if the row is out of range.
+    pub fn row(&self, row: usize) -> &[i64] {
+        let (columns, cells): (usize, &[i64]) = (self.columns(), &self.0);
+        &cells[row * columns..(row + 1) * columns]
+    }
+}
+
+impl Index<(usize, usize)> for CostMatrix {
+    type Output = i64;
+
+    fn index(&self, index: (usize, usize)) -> &i64 {
+        &self.0[index]
+    }
+}
+
+impl IndexMut<(usize, usize)> for CostMatrix {
+    fn index_mut(&mut self, index: (usize, usize)) -> &mut i64 {
+        &mut self.0[index]
+    }
+}
+
+/// Solver of the assignment between detections (rows) and trackers (columns) of a cost
+/// matrix, used by the Hungarian matching strategy.
+///
+/// Lower costs are better. The cost matrix may have more rows than columns or vice versa.
+/// The returned (row, column) pairs must not repeat a row or a column. Pairs outside the
+/// matrix or repeating a row or a column of an earlier pair are ignored, and pairs that do
+/// not pass the IoU threshold or the class check are rejected afterwards.
+/// With the Hungarian matching strategy the costs are scaled and perturbed before solving,
+/// so that ties between equal cost assignments are broken towards pairs that pass the
+/// checks and then towards pairing detections and trackers in index order.
+pub trait Matcher: Send + Sync {
+    /// Returns the assigned (row, column) pairs of the cost matrix.
+    fn solve(&self, cost_matrix: &CostMatrix) -> Vec<(usize, usize)>;
+}
+
+/// The default Matcher, the optimal assignment with the Hungarian algorithm.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HungarianMatcher;
+
+impl Matcher for HungarianMatcher {
+    fn solve(&self, cost_matrix: &CostMatrix) -> Vec<(usize, usize)> {
+        hungarian_assignment(cost_matrix)
+    }
+}
+
+impl AssociationMetric {
+    /// Calculates the similarity between the given bounding boxes scaled to the range [0, 1].
+    ///
+    /// GIoU, DIoU and CIoU are rescaled from [-1, 1] with (value + 1) / 2. Use
+    /// scaled_threshold to compare the similarity against an iou threshold.
+    pub fn similarity(&self, bbox_1: &BBox, bbox_2: &BBox) -> f64 {
+        self.rescale(match self {
+            AssociationMetric::Iou => bbox_1.iou(bbox_2),
+            AssociationMetric::Giou => bbox_1.giou(bbox_2),
+            AssociationMetric::Diou => bbox_1.diou(bbox_2),
+            AssociationMetric::Ciou => bbox_1.ciou(bbox_2),
+        })
+    }
+
+    /// Maps an iou threshold on the raw value of the metric to the scale of similarity.
+    ///
+    /// The thresholds of the config apply to the raw metric, so a pair passes a threshold
+    /// of 0.3 only if its GIoU, DIoU or CIoU is at least 0.3, and non-overlapping boxes
+    /// can only pass negative thresholds.
+    pub fn scaled_threshold(&self, iou_threshold: f64) -> f64 {
+        self.rescale(iou_threshold)
+    }
+
+    fn rescale(&self, value: f64) -> f64 {
+        match self {
+            AssociationMetric::Iou => value,
+            AssociationMetric::Giou | AssociationMetric::Diou | AssociationMetric::Ciou => {
+                (value + 1.0) / 2.0
+            }
+        }
+    }
+}
+
+/// The association stages of an OC-SORT update.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AssociationStage {
+    /// Association of the high score detections to the predicted trackers.
+    Primary,
+    /// BYTE association of the low score detections to the remaining trackers.
+    Byte,
+    /// Observation Centric Recovery of the remaining detections and trackers.
+    Ocr,
+}
+
+/// The inputs of the matching in a single association stage.
+#[derive(Clone, Debug)]
+pub struct StageDebug {
+    /// The association stage.
+    pub stage: AssociationStage,
+    /// The detection indices of the matrix rows.
+    pub detection_indices: Vec<usize>,
+    /// The tracker indices of the matrix columns.
+    pub tracker_indices: Vec<usize>,
+    /// The association metric similarity of each (detection, tracker) pair.
+    pub iou_matrix: Vec<Vec<f64>>,
+    /// The final cost of each (detection, tracker) pair passed to the solver.
+    pub cost_matrix: Vec<Vec<f64>>,
+}
+
+/// The association stages run during a single update, in order. Stages skipped
+/// because they had no detections or trackers to associate are not included.
+#[derive(Clone, Debug, Default)]
+pub struct FrameDebug {
+    /// The association stages that were run.
+    pub stages: Vec<StageDebug>,
+}
+
+impl FrameDebug {
+    fn record(
+        &mut self,
+        stage: AssociationStage,
+        detection_indices: &[usize],
+        tracker_indices: &[usize],
+        iou_matrix: &CostMatrix,
+        cost_matrix: &CostMatrix,
+        cost_multiplier: f64,
+    ) {
+        let to_rows = |matrix: &CostMatrix, sign: f64| -> Vec<Vec<f64>> {
+            (0..matrix.rows())
+                .map(|row| matrix.row(row))
+                .map(|row| {
+                    row.iter()
+                        .map(|&cell| sign * cell as f64 / cost_multiplier)
+                        .collect()
+                })
+                .collect()
+        };
+        self.stages.push(StageDebug {
+            stage,
+            detection_indices: detection_indices.to_vec(),
+            tracker_indices: tracker_indices.to_vec(),
+            iou_matrix: to_rows(iou_matrix, -1.0),
+            cost_matrix: to_rows(cost_matrix, 1.0),
+        });
+    }
+}
+
+/// Bounding box buffers reused across association stages and updates to avoid
+/// allocating them for every stage.
+#[derive(Debug, Default)]
+pub(crate) struct AssociationBuffers {
+    detection_bboxes: Vec<BBox>,
+    tracker_bboxes: Vec<BBox>,
+}
 
 /// Associates the given detections to the given trackers.
 ///
@@ -15,42 +257,273 @@ const IOU_MULTIPLIER: f64 = 10000.0;
 ///  - detection_indices: The indices of the detections available for association.
 ///  - trackers: Reference to all trackers.
 ///  - tracker_indices: The indices of the trackers available for association.
-///  - iou_threshold: The minimum iou score needed for a valid association.
+///  - config: The tracker configuration containing the association metric and threshold.
+///  - matcher: The solver of the assignment, e.g. HungarianMatcher. Not used with the
+///    Greedy matching strategy.
 ///
-/// Takes into account iou scores, observation centric momentum
-/// and class similarity.
+/// Takes into account iou scores, observation centric momentum,
+/// class similarity and optionally the area ratio.
 pub fn associate_detections_to_trackers(
     detections: &[Detection],
     detection_indices: &[usize],
     trackers: &[KalmanBoxTracker],
     tracker_indices: &[usize],
-    iou_threshold: f64,
+    config: &OCSortConfig,
+    matcher: &dyn Matcher,
 ) -> (Vec<(usize, usize)>, Vec<usize>, Vec<usize>) {
-    let (detection_bboxes, tracker_bboxes) =
-        get_bboxes(detections, detection_indices, trackers, tracker_indices);
+    run_primary_stage(
+        detections,
+        detection_indices,
+        trackers,
+        tracker_indices,
+        config,
+        matcher,
+        &mut AssociationBuffers::default(),
+        None,
+    )
+}
 
-    let iou_matrix = calc_iou_cost_matrix(&detection_bboxes, &tracker_bboxes);
-    let mut cost_matrix = iou_matrix.clone();
-    add_speed_cost_matrix(&detection_bboxes, &trackers, &mut cost_matrix);
-    add_class_cost_matrix(
+/// The (detection_index, tracker_index, iou) triples of the matches of a stage.
+type MatchesWithIou = Vec<(usize, usize, f64)>;
+
+/// Same as associate_detections_to_trackers, but returns each match as a
+/// (detection_index, tracker_index, iou) triple, e.g. to judge the confidence of the matches.
+///
+/// The iou is the association_metric similarity of the detection and the predicted bbox of
+/// the tracker, i.e. the IoU by default, or their mask IoU where the matching used it. It is
+/// computed exactly for each match, not read back from the integer cost matrix.
+pub fn associate_detections_to_trackers_with_iou(
+    detections: &[Detection],
+    detection_indices: &[usize],
+    trackers: &[KalmanBoxTracker],
+    tracker_indices: &[usize],
+    config: &OCSortConfig,
+    matcher: &dyn Matcher,
+) -> (MatchesWithIou, Vec<usize>, Vec<usize>) {
+    let (matched_indices, unmatched_detection_indices, unmatched_tracker_indices) =
+        run_primary_stage(
+            detections,
+            detection_indices,
+            trackers,
+            tracker_indices,
+            config,
+            matcher,
+            &mut AssociationBuffers::default(),
+            None,
+        );
+
+    let metric = config.association_metric;
+    let matched = matched_indices
+        .into_iter()
+        .map(|(detection_index, tracker_index)| {
+            let (detection, tracker) = (&detections[detection_index], &trackers[tracker_index]);
+            let iou = mask_similarity(detection, tracker, metric, false)
+                .unwrap_or_else(|| metric.similarity(&detection.bbox, &tracker.get_bbox()));
+            (detection_index, tracker_index, iou)
+        })
+        .collect();
+
+    (
+        matched,
+        unmatched_detection_indices,
+        unmatched_tracker_indices,
+    )
+}
+
+/// Builds the cost matrix of the primary association stage without solving the
+/// assignment, e.g. to visualize the costs or to solve it with another algorithm.
+///
+/// ## Args
+///  - detections: Reference to all detections.
+///  - detection_indices: The indices of the detections available for association.
+///  - trackers: Reference to all trackers.
+///  - tracker_indices: The indices of the trackers available for association.
+///  - config: The tracker configuration containing the association metric and weights.
+///
+/// Row i holds the costs of `detections[detection_indices[i]]` and column j those of
+/// `trackers[tracker_indices[j]]`. The cells are the sum of the negative IoU, the momentum
+/// cost and the optional cost terms of the config, multiplied by cost_multiplier and
+/// rounded towards zero. Pairs gated out by class_gating or mahalanobis_gate are at the
+/// largest cost.
+pub fn build_cost_matrix(
+    detections: &[Detection],
+    detection_indices: &[usize],
+    trackers: &[KalmanBoxTracker],
+    tracker_indices: &[usize],
+    config: &OCSortConfig,
+) -> CostMatrix {
+    let mut buffers = AssociationBuffers::default();
+    let (detection_bboxes, tracker_bboxes) = get_bboxes(
         detections,
         detection_indices,
         trackers,
         tracker_indices,
-        &mut cost_matrix,
+        &mut buffers,
+    );
+
+    build_primary_cost_matrices(
+        detections,
+        detection_indices,
+        detection_bboxes,
+        trackers,
+        tracker_indices,
+        tracker_bboxes,
+        config,
+    )
+    .1
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn run_primary_stage(
+    detections: &[Detection],
+    detection_indices: &[usize],
+    trackers: &[KalmanBoxTracker],
+    tracker_indices: &[usize],
+    config: &OCSortConfig,
+    matcher: &dyn Matcher,
+    buffers: &mut AssociationBuffers,
+    debug: Option<&mut FrameDebug>,
+) -> (Vec<(usize, usize)>, Vec<usize>, Vec<usize>) {
+    if detection_indices.is_empty() || tracker_indices.is_empty() {
+        return (
+            Vec::new(),
+            Vec::from(detection_indices),
+            Vec::from(tracker_indices),
+        );
+    }
+    let (detection_bboxes, tracker_bboxes) = get_bboxes(
+        detections,
+        detection_indices,
+        trackers,
+        tracker_indices,
+        buffers,
+    );
+
+    let (iou_matrix, cost_matrix) = build_primary_cost_matrices(
+        detections,
+        detection_indices,
+        detection_bboxes,
+        trackers,
+        tracker_indices,
+        tracker_bboxes,
+        config,
     );
 
+    if let Some(debug) = debug {
+        debug.record(
+            AssociationStage::Primary,
+            detection_indices,
+            tracker_indices,
+            &iou_matrix,
+            &cost_matrix,
+            config.cost_multiplier,
+        );
+    }
+
     calculate_matching(
         detections,
         detection_indices,
         trackers,
         tracker_indices,
-        &cost_matrix,
+        cost_matrix,
         &iou_matrix,
-        iou_threshold,
+        config.iou_threshold,
+        config,
+        matcher,
     )
 }
 
+/// Builds the IoU matrix and the full cost matrix of the primary stage, with the rows
+/// following detection_indices and the columns tracker_indices.
+fn build_primary_cost_matrices(
+    detections: &[Detection],
+    detection_indices: &[usize],
+    detection_bboxes: &[BBox],
+    trackers: &[KalmanBoxTracker],
+    tracker_indices: &[usize],
+    tracker_bboxes: &[BBox],
+    config: &OCSortConfig,
+) -> (CostMatrix, CostMatrix) {
+    let mut iou_matrix = calc_iou_cost_matrix(
+        detection_bboxes,
+        tracker_bboxes,
+        config.association_metric,
+        config.cost_multiplier,
+    );
+    apply_mask_iou(
+        detections,
+        detection_indices,
+        trackers,
+        tracker_indices,
+        config.association_metric,
+        config.cost_multiplier,
+        false,
+        &mut iou_matrix,
+    );
+    let mut cost_matrix = iou_matrix.clone();
+    add_speed_cost_matrix(
+        detection_bboxes,
+        trackers,
+        tracker_indices,
+        config.inertia_weight,
+        config.cost_multiplier,
+        &mut cost_matrix,
+    );
+    if config.appearance_weight > 0.0 {
+        add_appearance_cost_matrix(
+            detections,
+            detection_indices,
+            trackers,
+            tracker_indices,
+            config.appearance_weight,
+            config.cost_multiplier,
+            &mut cost_matrix,
+        );
+    }
+    if config.area_ratio_weight > 0.0 {
+        add_area_ratio_cost_matrix(
+            detection_bboxes,
+            tracker_bboxes,
+            config.area_ratio_weight,
+            config.cost_multiplier,
+            &mut cost_matrix,
+        );
+    }
+    if config.use_ext_ids {
+        add_ext_id_cost_matrix(
+            detections,
+            detection_indices,
+            trackers,
+            tracker_indices,
+            config.cost_multiplier,
+            &mut cost_matrix,
+        );
+    }
+    if !config.class_agnostic {
+        add_class_cost_matrix(
+            detections,
+            detection_indices,
+            trackers,
+            tracker_indices,
+            config.class_gating,
+            config.cost_multiplier,
+            &mut cost_matrix,
+        );
+    }
+    if let Some(mahalanobis_gate) = config.mahalanobis_gate {
+        add_mahalanobis_gate(
+            detection_bboxes,
+            trackers,
+            tracker_indices,
+            mahalanobis_gate,
+            config,
+            &mut cost_matrix,
+        );
+    }
+
+    (iou_matrix, cost_matrix)
+}
+
 /// Runs BYTE association, i.e, associates the low score detections to the current
 /// position of the trackers by only considering iou and class similarity.
 ///
@@ -59,13 +532,41 @@ pub fn associate_detections_to_trackers(
 ///  - detection_indices: The indices of detections with a low score.
 ///  - trackers: Reference to all trackers.
 ///  - tracker_indices: The indices of trackers available for association.
-///  - iou_threshold: The minimum iou score needed for a valid association.
+///  - config: The tracker configuration containing the association metric and threshold.
+///  - matcher: The solver of the assignment, e.g. HungarianMatcher. Not used with the
+///    Greedy matching strategy.
+///
+/// Uses byte_iou_threshold if set and otherwise iou_threshold.
 pub fn byte_associate(
     detections: &[Detection],
     detection_indices: &[usize],
     trackers: &[KalmanBoxTracker],
     tracker_indices: &[usize],
-    iou_threshold: f64,
+    config: &OCSortConfig,
+    matcher: &dyn Matcher,
+) -> (Vec<(usize, usize)>, Vec<usize>, Vec<usize>) {
+    run_byte_stage(
+        detections,
+        detection_indices,
+        trackers,
+        tracker_indices,
+        config,
+        matcher,
+        &mut AssociationBuffers::default(),
+        None,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn run_byte_stage(
+    detections: &[Detection],
+    detection_indices: &[usize],
+    trackers: &[KalmanBoxTracker],
+    tracker_indices: &[usize],
+    config: &OCSortConfig,
+    matcher: &dyn Matcher,
+    buffers: &mut AssociationBuffers,
+    debug: Option<&mut FrameDebug>,
 ) -> (Vec<(usize, usize)>, Vec<usize>, Vec<usize>) {
     if detection_indices.is_empty() || tracker_indices.is_empty() {
         return (
@@ -74,27 +575,93 @@ pub fn byte_associate(
             Vec::from(tracker_indices),
         );
     }
-    let (detection_bboxes, tracker_bboxes) =
-        get_bboxes(detections, detection_indices, trackers, tracker_indices);
+    let (detection_bboxes, tracker_bboxes) = get_bboxes(
+        detections,
+        detection_indices,
+        trackers,
+        tracker_indices,
+        buffers,
+    );
 
-    let iou_matrix = calc_iou_cost_matrix(&detection_bboxes, &tracker_bboxes);
-    let mut cost_matrix = iou_matrix.clone();
-    add_class_cost_matrix(
+    let mut iou_matrix = calc_iou_cost_matrix(
+        detection_bboxes,
+        tracker_bboxes,
+        config.association_metric,
+        config.cost_multiplier,
+    );
+    apply_mask_iou(
         detections,
         detection_indices,
         trackers,
         tracker_indices,
-        &mut cost_matrix,
+        config.association_metric,
+        config.cost_multiplier,
+        false,
+        &mut iou_matrix,
     );
+    let mut cost_matrix = iou_matrix.clone();
+    if config.area_ratio_weight > 0.0 {
+        add_area_ratio_cost_matrix(
+            detection_bboxes,
+            tracker_bboxes,
+            config.area_ratio_weight,
+            config.cost_multiplier,
+            &mut cost_matrix,
+        );
+    }
+    if config.use_ext_ids {
+        add_ext_id_cost_matrix(
+            detections,
+            detection_indices,
+            trackers,
+            tracker_indices,
+            config.cost_multiplier,
+            &mut cost_matrix,
+        );
+    }
+    if !config.class_agnostic {
+        add_class_cost_matrix(
+            detections,
+            detection_indices,
+            trackers,
+            tracker_indices,
+            config.class_gating,
+            config.cost_multiplier,
+            &mut cost_matrix,
+        );
+    }
+    if let Some(mahalanobis_gate) = config.mahalanobis_gate {
+        add_mahalanobis_gate(
+            detection_bboxes,
+            trackers,
+            tracker_indices,
+            mahalanobis_gate,
+            config,
+            &mut cost_matrix,
+        );
+    }
+
+    if let Some(debug) = debug {
+        debug.record(
+            AssociationStage::Byte,
+            detection_indices,
+            tracker_indices,
+            &iou_matrix,
+            &cost_matrix,
+            config.cost_multiplier,
+        );
+    }
 
     calculate_matching(
         detections,
         detection_indices,
         trackers,
         tracker_indices,
-        &cost_matrix,
+        cost_matrix,
         &iou_matrix,
-        iou_threshold,
+        config.byte_iou_threshold.unwrap_or(config.iou_threshold),
+        config,
+        matcher,
     )
 }
 
@@ -107,13 +674,41 @@ pub fn byte_associate(
 ///  - detection_indices: The indices of detections available for association.
 ///  - trackers: Reference to all trackers.
 ///  - tracker_indices: The indices of trackers available for association.
-///  - iou_threshold: The minimum iou score needed for a valid association.
+///  - config: The tracker configuration containing the association metric and threshold.
+///  - matcher: The solver of the assignment, e.g. HungarianMatcher. Not used with the
+///    Greedy matching strategy.
+///
+/// Uses ocr_iou_threshold if set and otherwise iou_threshold.
 pub fn observation_centric_recovery(
     detections: &[Detection],
     detection_indices: &[usize],
     trackers: &[KalmanBoxTracker],
     tracker_indices: &[usize],
-    iou_threshold: f64,
+    config: &OCSortConfig,
+    matcher: &dyn Matcher,
+) -> (Vec<(usize, usize)>, Vec<usize>, Vec<usize>) {
+    run_ocr_stage(
+        detections,
+        detection_indices,
+        trackers,
+        tracker_indices,
+        config,
+        matcher,
+        &mut AssociationBuffers::default(),
+        None,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn run_ocr_stage(
+    detections: &[Detection],
+    detection_indices: &[usize],
+    trackers: &[KalmanBoxTracker],
+    tracker_indices: &[usize],
+    config: &OCSortConfig,
+    matcher: &dyn Matcher,
+    buffers: &mut AssociationBuffers,
+    debug: Option<&mut FrameDebug>,
 ) -> (Vec<(usize, usize)>, Vec<usize>, Vec<usize>) {
     if detection_indices.is_empty() || tracker_indices.is_empty() {
         return (
@@ -122,164 +717,624 @@ pub fn observation_centric_recovery(
             Vec::from(tracker_indices),
         );
     }
-    let detection_bboxes: Vec<BBox> = detection_indices
-        .iter()
-        .map(|&detection_index| detections[detection_index].bbox)
-        .collect();
-
-    let tracker_observations: Vec<BBox> = tracker_indices
-        .iter()
-        .map(|&tracker_index| *trackers[tracker_index].get_last_observation())
-        .collect();
+    let AssociationBuffers {
+        detection_bboxes,
+        tracker_bboxes: tracker_observations,
+    } = buffers;
+    detection_bboxes.clear();
+    detection_bboxes.extend(
+        detection_indices
+            .iter()
+            .map(|&detection_index| detections[detection_index].bbox),
+    );
+    tracker_observations.clear();
+    tracker_observations.extend(
+        tracker_indices
+            .iter()
+            .map(|&tracker_index| *trackers[tracker_index].get_last_observation()),
+    );
 
-    let iou_matrix = calc_iou_cost_matrix(&detection_bboxes, &tracker_observations);
-    let mut cost_matrix = iou_matrix.clone();
-    add_class_cost_matrix(
+    let mut iou_matrix = calc_iou_cost_matrix(
+        detection_bboxes,
+        tracker_observations,
+        config.association_metric,
+        config.cost_multiplier,
+    );
+    apply_mask_iou(
         detections,
         detection_indices,
         trackers,
         tracker_indices,
-        &mut cost_matrix,
+        config.association_metric,
+        config.cost_multiplier,
+        true,
+        &mut iou_matrix,
     );
+    let mut cost_matrix = iou_matrix.clone();
+    if config.area_ratio_weight > 0.0 {
+        add_area_ratio_cost_matrix(
+            detection_bboxes,
+            tracker_observations,
+            config.area_ratio_weight,
+            config.cost_multiplier,
+            &mut cost_matrix,
+        );
+    }
+    if config.use_ext_ids {
+        add_ext_id_cost_matrix(
+            detections,
+            detection_indices,
+            trackers,
+            tracker_indices,
+            config.cost_multiplier,
+            &mut cost_matrix,
+        );
+    }
+    if !config.class_agnostic {
+        add_class_cost_matrix(
+            detections,
+            detection_indices,
+            trackers,
+            tracker_indices,
+            config.class_gating,
+            config.cost_multiplier,
+            &mut cost_matrix,
+        );
+    }
+
+    if let Some(debug) = debug {
+        debug.record(
+            AssociationStage::Ocr,
+            detection_indices,
+            tracker_indices,
+            &iou_matrix,
+            &cost_matrix,
+            config.cost_multiplier,
+        );
+    }
 
     calculate_matching(
         detections,
         detection_indices,
         trackers,
         tracker_indices,
-        &cost_matrix,
+        cost_matrix,
         &iou_matrix,
-        iou_threshold,
+        config.ocr_iou_threshold.unwrap_or(config.iou_threshold),
+        config,
+        matcher,
     )
 }
 
-fn get_bboxes(
+/// Fills the buffers with the bounding boxes of the given detections and the
+/// predicted bounding boxes of the given trackers.
+fn get_bboxes<'a>(
     detections: &[Detection],
     detection_indices: &[usize],
     trackers: &[KalmanBoxTracker],
     tracker_indices: &[usize],
-) -> (Vec<BBox>, Vec<BBox>) {
-    let detection_bboxes: Vec<BBox> = detection_indices
-        .iter()
-        .map(|&detection_index| detections[detection_index].bbox)
-        .collect();
+    buffers: &'a mut AssociationBuffers,
+) -> (&'a [BBox], &'a [BBox]) {
+    buffers.detection_bboxes.clear();
+    buffers.detection_bboxes.extend(
+        detection_indices
+            .iter()
+            .map(|&detection_index| detections[detection_index].bbox),
+    );
 
-    let tracker_bboxes: Vec<BBox> = tracker_indices
-        .iter()
-        .map(|&tracker_index| trackers[tracker_index].get_bbox())
-        .collect();
+    buffers.tracker_bboxes.clear();
+    buffers.tracker_bboxes.extend(
+        tracker_indices
+            .iter()
+            .map(|&tracker_index| trackers[tracker_index].get_bbox()),
+    );
 
-    (detection_bboxes, tracker_bboxes)
+    (&buffers.detection_bboxes, &buffers.tracker_bboxes)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn calculate_matching(
     detections: &[Detection],
     detection_indices: &[usize],
     trackers: &[KalmanBoxTracker],
     tracker_indices: &[usize],
-    cost_matrix: &Matrix<i64>,
-    iou_matrix: &Matrix<i64>,
+    mut cost_matrix: CostMatrix,
+    iou_matrix: &CostMatrix,
     iou_threshold: f64,
+    config: &OCSortConfig,
+    matcher: &dyn Matcher,
 ) -> (Vec<(usize, usize)>, Vec<usize>, Vec<usize>) {
-    let transpose = cost_matrix.rows > cost_matrix.columns;
-    let weights = if transpose {
-        &cost_matrix.transposed()
-    } else {
-        cost_matrix
+    let passes_checks = |row: usize, column: usize| {
+        let tracker = &trackers[tracker_indices[column]];
+        let iou_threshold = config
+            .iou_threshold_schedule
+            .map_or(iou_threshold, |schedule| {
+                schedule.threshold(iou_threshold, tracker.time_since_update)
+            });
+        let iou_threshold = config.association_metric.scaled_threshold(iou_threshold);
+        let valid_iou =
+            -iou_matrix[(row, column)] >= (iou_threshold * config.cost_multiplier) as i64;
+        let valid_class =
+            config.class_agnostic || detections[detection_indices[row]].class == tracker.class;
+        valid_iou && valid_class
+    };
+    if config.matching_strategy == MatchingStrategy::Hungarian {
+        break_ties(&mut cost_matrix, passes_checks);
+    }
+    // Cells at MAX_COST are gated out.
+    let is_valid = |row: usize, column: usize| {
+        passes_checks(row, column) && cost_matrix[(row, column)] < MAX_COST
     };
-    let assignment_vector = kuhn_munkres_min(weights).1;
-    let assigned: HashSet<usize> = assignment_vector.iter().cloned().collect();
 
-    let mut unmatched_detections = if transpose {
-        (0..weights.columns)
-            .filter(|col| !assigned.contains(col))
-            .collect()
-    } else {
-        Vec::new()
+    let mut assignment = match config.matching_strategy {
+        MatchingStrategy::Hungarian => matcher.solve(&cost_matrix),
+        MatchingStrategy::Greedy => greedy_assignment(&cost_matrix, is_valid),
     };
-    let mut unmatched_trackers = if transpose {
-        Vec::new()
+
+    let mut detection_matched = vec![false; detection_indices.len()];
+    let mut tracker_matched = vec![false; tracker_indices.len()];
+    assignment.retain(|&(row, column)| {
+        // A custom matcher may return pairs outside the matrix or reuse a row or column.
+        if row >= detection_indices.len()
+            || column >= tracker_indices.len()
+            || detection_matched[row]
+            || tracker_matched[column]
+            || !is_valid(row, column)
+        {
+            return false;
+        }
+        detection_matched[row] = true;
+        tracker_matched[column] = true;
+        true
+    });
+    let matched: Vec<(usize, usize)> = assignment
+        .iter()
+        .map(|&(row, column)| (detection_indices[row], tracker_indices[column]))
+        .collect();
+
+    let unmatched_detections: Vec<usize> = detection_indices
+        .iter()
+        .zip(detection_matched)
+        .filter_map(|(&detection_index, matched)| (!matched).then_some(detection_index))
+        .collect();
+    let unmatched_trackers: Vec<usize> = tracker_indices
+        .iter()
+        .zip(tracker_matched)
+        .filter_map(|(&tracker_index, matched)| (!matched).then_some(tracker_index))
+        .collect();
+
+    debug_assert!(
+        is_permutation(
+            matched.iter().map(|&(detection_index, _)| detection_index),
+            &unmatched_detections,
+            detection_indices
+        ),
+        "matched and unmatched detections must contain each detection index exactly once"
+    );
+    debug_assert!(
+        is_permutation(
+            matched.iter().map(|&(_, tracker_index)| tracker_index),
+            &unmatched_trackers,
+            tracker_indices
+        ),
+        "matched and unmatched trackers must contain each tracker index exactly once"
+    );
+
+    (matched, unmatched_detections, unmatched_trackers)
+}
+
+/// Returns true if the matched and unmatched indices together are a permutation of the
+/// input indices.
+fn is_permutation(
+    matched: impl Iterator<Item = usize>,
+    unmatched: &[usize],
+    indices: &[usize],
+) -> bool {
+    matched
+        .chain(unmatched.iter().copied())
+        .sorted_unstable()
+        .eq(indices.iter().copied().sorted_unstable())
+}
+
+/// Solves the optimal assignment of the cost matrix with the Hungarian algorithm.
+/// Returns the assigned (row, column) pairs sorted by row.
+///
+/// Ties between equal cost assignments are broken beforehand with break_ties.
+fn hungarian_assignment(cost_matrix: &CostMatrix) -> Vec<(usize, usize)> {
+    let mut assignment: Vec<(usize, usize)> = if cost_matrix.rows() > cost_matrix.columns() {
+        kuhn_munkres_min(&cost_matrix.0.transposed())
+            .1
+            .into_iter()
+            .enumerate()
+            .map(|(column, row)| (row, column))
+            .collect()
     } else {
-        (0..weights.columns)
-            .filter(|col| !assigned.contains(col))
+        kuhn_munkres_min(&cost_matrix.0)
+            .1
+            .into_iter()
+            .enumerate()
             .collect()
     };
 
-    let mut matched = Vec::new();
+    assignment.sort_unstable();
+    assignment
+}
 
-    for (i, &j) in assignment_vector.iter().enumerate() {
-        let (detection_indices_index, tracker_indices_index) =
-            if transpose { (j, i) } else { (i, j) };
+/// Scales the costs and perturbs them so that among assignments of equal cost the solver
+/// prefers, in order:
+///  - The assignment with the fewest pairs that fail the IoU or the class check.
+///  - The assignment pairing lower rows (detections) with lower columns (trackers). The
+///    pair (row, column) is perturbed by column * (rows - row) + row * (columns - column),
+///    so among full assignments of a square matrix the pairing in index order is the
+///    unique minimum, whatever the solver's search order.
+///
+/// The scale is larger than the total perturbation of any assignment, so a lower cost
+/// assignment stays lower. Gated cells stay at MAX_COST. The costs are left unperturbed if
+/// the scaled costs would not fit below MAX_COST, e.g. for very large frames.
+fn break_ties(cost_matrix: &mut CostMatrix, passes_checks: impl Fn(usize, usize) -> bool + Sync) {
+    let (rows, columns) = (cost_matrix.rows() as i64, cost_matrix.columns() as i64);
+    let pairs = rows.min(columns);
+    // Bounds of the index term of a single cell and of all cells of an assignment.
+    let max_index_term = 2 * rows * columns;
+    let Some(failed_check_penalty) = pairs.checked_mul(max_index_term) else {
+        return;
+    };
+    let Some(scale) = failed_check_penalty
+        .checked_add(max_index_term)
+        .and_then(|cell_perturbation| pairs.checked_mul(cell_perturbation))
+    else {
+        return;
+    };
+    let max_cost = cost_matrix
+        .0
+        .values()
+        .filter(|&&cost| cost < MAX_COST)
+        .map(|cost| cost.abs())
+        .max()
+        .unwrap_or(0);
+    if max_cost
+        .checked_mul(scale)
+        .and_then(|scaled| scaled.checked_add(scale))
+        .is_none_or(|scaled| scaled >= MAX_COST)
+    {
+        return;
+    }
 
-        let detection_index = detection_indices[detection_indices_index];
-        let tracker_index = tracker_indices[tracker_indices_index];
-        let detection = &detections[detection_index];
-        let tracker = &trackers[tracker_index];
+    for_each_cell(cost_matrix, |(row, column), cell| {
+        if *cell == MAX_COST {
+            return;
+        }
+        let (row_index, column_index) = (row as i64, column as i64);
+        let index_term = column_index * (rows - row_index) + row_index * (columns - column_index);
+        let penalty = if passes_checks(row, column) {
+            0
+        } else {
+            failed_check_penalty
+        };
+        *cell = *cell * scale + penalty + index_term;
+    });
+}
 
-        let invalid_iou = -iou_matrix[(detection_indices_index, tracker_indices_index)]
-            < (iou_threshold * IOU_MULTIPLIER) as i64;
-        let invalid_class = detection.as_ref().class != tracker.as_ref().class;
+/// Assigns the valid (row, column) pairs of the cost matrix by picking the lowest cost
+/// pairs first. Rows and columns without a valid pair are left unassigned.
+fn greedy_assignment(
+    cost_matrix: &CostMatrix,
+    is_valid: impl Fn(usize, usize) -> bool,
+) -> Vec<(usize, usize)> {
+    let mut candidates: Vec<(i64, usize, usize)> = (0..cost_matrix.rows())
+        .cartesian_product(0..cost_matrix.columns())
+        .filter(|&(row, column)| is_valid(row, column))
+        .map(|(row, column)| (cost_matrix[(row, column)], row, column))
+        .collect();
+    candidates.sort_unstable();
 
-        if invalid_iou || invalid_class {
-            unmatched_detections.push(detection_index);
-            unmatched_trackers.push(tracker_index);
-            continue;
+    let mut row_taken = vec![false; cost_matrix.rows()];
+    let mut column_taken = vec![false; cost_matrix.columns()];
+    let mut assignment = Vec::new();
+    for (_, row, column) in candidates {
+        if !row_taken[row] && !column_taken[column] {
+            row_taken[row] = true;
+            column_taken[column] = true;
+            assignment.push((row, column));
         }
-        matched.push((detection_index, tracker_index));
     }
+    assignment.sort_unstable();
 
-    (matched, unmatched_detections, unmatched_trackers)
+    assignment
 }
 
-fn calc_iou_cost_matrix(bboxes_1: &[BBox], bboxes_2: &[BBox]) -> Matrix<i64> {
-    let rows = bboxes_1.len();
-    let columns = bboxes_2.len();
+fn calc_iou_cost_matrix(
+    bboxes_1: &[BBox],
+    bboxes_2: &[BBox],
+    metric: AssociationMetric,
+    cost_multiplier: f64,
+) -> CostMatrix {
+    let mut matrix = CostMatrix::new(bboxes_1.len(), bboxes_2.len(), 0);
 
-    let mut matrix = Matrix::new(rows, columns, 0);
+    for_each_cell(&mut matrix, |(i, j), cell| {
+        *cell = to_cost(
+            -metric.similarity(&bboxes_1[i], &bboxes_2[j]),
+            cost_multiplier,
+        );
+    });
 
-    for (i, bbox_1) in bboxes_1.iter().enumerate() {
-        for (j, bbox_2) in bboxes_2.iter().enumerate() {
-            matrix[(i, j)] = -(bbox_1.iou(bbox_2) * IOU_MULTIPLIER) as i64;
-        }
+    matrix
+}
+
+/// Replaces the similarity of the pairs where both the detection and the last associated
+/// detection of the tracker have a segmentation mask of the same size with the mask IoU,
+/// on the scale of the association metric. The other pairs keep their bbox similarity.
+///
+/// The mask of the tracker is where the object was at its last association. The primary
+/// and BYTE stages compare the detections with the predicted bbox, so there the mask is
+/// only used if the tracker was associated in the previous update. The OCR stage compares
+/// with the last observation, which the mask belongs to, so it sets last_observation.
+#[allow(clippy::too_many_arguments)]
+fn apply_mask_iou(
+    detections: &[Detection],
+    detection_indices: &[usize],
+    trackers: &[KalmanBoxTracker],
+    tracker_indices: &[usize],
+    metric: AssociationMetric,
+    cost_multiplier: f64,
+    last_observation: bool,
+    iou_matrix: &mut CostMatrix,
+) {
+    if detection_indices
+        .iter()
+        .all(|&detection_index| detections[detection_index].mask.is_none())
+    {
+        return;
     }
 
-    matrix
+    for_each_cell(iou_matrix, |(i, j), cell| {
+        if let Some(similarity) = mask_similarity(
+            &detections[detection_indices[i]],
+            &trackers[tracker_indices[j]],
+            metric,
+            last_observation,
+        ) {
+            *cell = to_cost(-similarity, cost_multiplier);
+        }
+    });
 }
 
+/// Returns the mask IoU of the detection and the tracker on the scale of the association
+/// metric, or None if apply_mask_iou keeps the bbox similarity of the pair.
+fn mask_similarity(
+    detection: &Detection,
+    tracker: &KalmanBoxTracker,
+    metric: AssociationMetric,
+    last_observation: bool,
+) -> Option<f64> {
+    if !last_observation && tracker.time_since_update > 1 {
+        return None;
+    }
+    let (detection_mask, tracker_mask) = (detection.mask.as_ref()?, tracker.mask.as_ref()?);
+    ((detection_mask.height, detection_mask.width) == (tracker_mask.height, tracker_mask.width))
+        .then(|| metric.rescale(mask_iou(detection_mask, tracker_mask)))
+}
+
+/// Penalizes the pairs with mismatching classes. With class gating the mismatching cells
+/// are set to MAX_COST so that the solver avoids them whenever possible. Should be
+/// applied after all other costs.
 fn add_class_cost_matrix(
     detections: &[Detection],
     detection_indices: &[usize],
     trackers: &[KalmanBoxTracker],
     tracker_indices: &[usize],
-    cost_matrix: &mut Matrix<i64>,
+    class_gating: bool,
+    cost_multiplier: f64,
+    cost_matrix: &mut CostMatrix,
 ) {
-    for (i, &detection_index) in detection_indices.iter().enumerate() {
-        for (j, &tracker_index) in tracker_indices.iter().enumerate() {
-            let cost = if detections[detection_index].class == trackers[tracker_index].class {
-                0
+    for_each_cell(cost_matrix, |(i, j), cell| {
+        let detection_class = detections[detection_indices[i]].class;
+        let tracker_class = trackers[tracker_indices[j]].class;
+        if detection_class != tracker_class {
+            if class_gating {
+                *cell = MAX_COST;
             } else {
-                (100.0 * IOU_MULTIPLIER) as i64
-            };
-            cost_matrix[(i, j)] += cost;
+                add_cost(cell, 100.0, cost_multiplier);
+            }
         }
+    });
+}
+
+/// Sets the cells of the pairs whose squared Mahalanobis distance exceeds the gate to
+/// MAX_COST. Should be applied after all other costs.
+fn add_mahalanobis_gate(
+    detection_bboxes: &[BBox],
+    trackers: &[KalmanBoxTracker],
+    tracker_indices: &[usize],
+    mahalanobis_gate: f64,
+    config: &OCSortConfig,
+    cost_matrix: &mut CostMatrix,
+) {
+    for_each_cell(cost_matrix, |(i, j), cell| {
+        let tracker = &trackers[tracker_indices[j]];
+        if tracker.mahalanobis_distance(&detection_bboxes[i], config) > mahalanobis_gate {
+            *cell = MAX_COST;
+        }
+    });
+}
+
+/// Adds the cosine distance between the detection and tracker embeddings multiplied by the
+/// weight. Pairs where either embedding is missing, empty or of a different length are
+/// left unchanged.
+fn add_appearance_cost_matrix(
+    detections: &[Detection],
+    detection_indices: &[usize],
+    trackers: &[KalmanBoxTracker],
+    tracker_indices: &[usize],
+    appearance_weight: f64,
+    cost_multiplier: f64,
+    cost_matrix: &mut CostMatrix,
+) {
+    for_each_cell(cost_matrix, |(i, j), cell| {
+        let detection_embedding = detections[detection_indices[i]].embedding.as_deref();
+        let tracker_embedding = trackers[tracker_indices[j]].embedding.as_deref();
+        if let (Some(detection_embedding), Some(tracker_embedding)) =
+            (detection_embedding, tracker_embedding)
+            && let Some(distance) = cosine_distance(detection_embedding, tracker_embedding)
+        {
+            add_cost(cell, appearance_weight * distance, cost_multiplier);
+        }
+    });
+}
+
+/// Returns 1 - the cosine similarity of the vectors, or None if the lengths differ or
+/// either vector is zero.
+fn cosine_distance(embedding_1: &[f32], embedding_2: &[f32]) -> Option<f64> {
+    if embedding_1.len() != embedding_2.len() {
+        return None;
+    }
+    let (dot, norm_1, norm_2) = embedding_1.iter().zip(embedding_2).fold(
+        (0.0, 0.0, 0.0),
+        |(dot, norm_1, norm_2), (&value_1, &value_2)| {
+            let (value_1, value_2) = (f64::from(value_1), f64::from(value_2));
+            (
+                dot + value_1 * value_2,
+                norm_1 + value_1 * value_1,
+                norm_2 + value_2 * value_2,
+            )
+        },
+    );
+    if norm_1 == 0.0 || norm_2 == 0.0 {
+        return None;
     }
+
+    Some(1.0 - dot / (norm_1.sqrt() * norm_2.sqrt()))
+}
+
+/// Favors the pairs where the detection and the tracker have the same external id and
+/// penalizes the pairs where both have an external id but they differ.
+fn add_ext_id_cost_matrix(
+    detections: &[Detection],
+    detection_indices: &[usize],
+    trackers: &[KalmanBoxTracker],
+    tracker_indices: &[usize],
+    cost_multiplier: f64,
+    cost_matrix: &mut CostMatrix,
+) {
+    for_each_cell(cost_matrix, |(i, j), cell| {
+        let detection_ext_id = detections[detection_indices[i]].ext_id;
+        let tracker_ext_id = trackers[tracker_indices[j]].ext_id;
+        if let (Some(detection_ext_id), Some(tracker_ext_id)) = (detection_ext_id, tracker_ext_id) {
+            add_cost(
+                cell,
+                if detection_ext_id == tracker_ext_id {
+                    -1.0
+                } else {
+                    1.0
+                },
+                cost_multiplier,
+            );
+        }
+    });
 }
 
 fn add_speed_cost_matrix(
     detection_bboxes: &[BBox],
     trackers: &[KalmanBoxTracker],
-    cost_matrix: &mut Matrix<i64>,
+    tracker_indices: &[usize],
+    inertia_weight: f64,
+    cost_multiplier: f64,
+    cost_matrix: &mut CostMatrix,
+) {
+    // Trackers without a speed direction have no momentum to compare against.
+    let tracker_motions: Vec<Option<(SVector<f64, 2>, &BBox)>> = tracker_indices
+        .iter()
+        .map(|&tracker_index| {
+            let tracker = &trackers[tracker_index];
+            tracker.has_speed_direction().then(|| {
+                (
+                    tracker.speed_direction,
+                    tracker.get_observation_dt_time_steps_away(),
+                )
+            })
+        })
+        .collect();
+
+    for_each_cell(cost_matrix, |(i, j), cell| {
+        let Some((inertia, bbox_2)) = tracker_motions[j] else {
+            return;
+        };
+        let speed_direction = detection_bboxes[i].speed_direction(bbox_2);
+
+        // Rounding can push the dot product of unit vectors slightly outside [-1, 1].
+        let diff_angle = inertia.dot(&speed_direction).clamp(-1.0, 1.0).acos();
+        let diff_angle_cost = (diff_angle - PI) / PI;
+
+        add_cost(cell, diff_angle_cost * inertia_weight, cost_multiplier);
+    });
+}
+
+/// Adds a cost of |ln(detection area / tracker area)| scaled by the weight, penalizing
+/// large changes in scale. Pairs with a zero area box are not penalized.
+fn add_area_ratio_cost_matrix(
+    detection_bboxes: &[BBox],
+    tracker_bboxes: &[BBox],
+    area_ratio_weight: f64,
+    cost_multiplier: f64,
+    cost_matrix: &mut CostMatrix,
 ) {
-    for (i, bbox_1) in detection_bboxes.iter().enumerate() {
-        for (j, tracker) in trackers.iter().enumerate() {
-            let inertia = tracker.speed_direction;
-            let bbox_2 = tracker.get_observation_dt_time_steps_away();
-            let speed_direction = bbox_1.speed_direction(bbox_2);
+    for_each_cell(cost_matrix, |(i, j), cell| {
+        let detection_area = detection_bboxes[i].area();
+        let tracker_area = tracker_bboxes[j].area();
+        if detection_area <= 0.0 || tracker_area <= 0.0 {
+            return;
+        }
+        let area_ratio_cost = (detection_area / tracker_area).ln().abs();
+
+        add_cost(cell, area_ratio_cost * area_ratio_weight, cost_multiplier);
+    });
+}
+
+/// Converts a cost term to a cost matrix cell by scaling it with the cost multiplier,
+/// since the Hungarian solver only accepts integer weights. Non-finite terms become
+/// MAX_COST and the cell is clamped to [-MAX_COST, MAX_COST].
+fn to_cost(value: f64, cost_multiplier: f64) -> i64 {
+    if !value.is_finite() {
+        return MAX_COST;
+    }
+    ((value * cost_multiplier) as i64).clamp(-MAX_COST, MAX_COST)
+}
+
+/// Adds the cost term to the cell, saturating at [-MAX_COST, MAX_COST].
+fn add_cost(cell: &mut i64, value: f64, cost_multiplier: f64) {
+    *cell = cell
+        .saturating_add(to_cost(value, cost_multiplier))
+        .clamp(-MAX_COST, MAX_COST);
+}
 
-            let diff_angle = inertia.dot(&speed_direction).acos();
-            let diff_angle_cost = (diff_angle - PI) / PI;
+/// Applies the given operation to every ((row, column), cell) of the matrix.
+///
+/// With the rayon feature, matrices with at least PARALLEL_THRESHOLD cells are processed
+/// in parallel over the rows. Each cell is computed independently, so the result is
+/// identical to the serial path.
+fn for_each_cell(matrix: &mut CostMatrix, op: impl Fn((usize, usize), &mut i64) + Sync) {
+    let columns = matrix.columns();
+    if columns == 0 {
+        return;
+    }
+
+    #[cfg(feature = "rayon")]
+    if matrix.rows() * columns >= PARALLEL_THRESHOLD {
+        matrix
+            .0
+            .par_chunks_mut(columns)
+            .enumerate()
+            .for_each(|(i, row)| {
+                for (j, cell) in row.iter_mut().enumerate() {
+                    op((i, j), cell);
+                }
+            });
+        return;
+    }
 
-            cost_matrix[(i, j)] += (diff_angle_cost * 0.2 * IOU_MULTIPLIER) as i64;
+    for (i, row) in matrix.0.chunks_mut(columns).enumerate() {
+        for (j, cell) in row.iter_mut().enumerate() {
+            op((i, j), cell);
         }
     }
 }
@@ -287,27 +1342,28 @@ fn add_speed_cost_matrix(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        config::{CHI_SQUARED_95_4_DOF, IouThresholdSchedule},
+        mask::RleMask,
+    };
 
     #[test]
     fn test_associate_detections_to_trackers_returns_correct_matching() {
         let detections = vec![
-            Detection {
-                bbox: BBox::new(0.0, 0.0, 1.0, 1.0),
-                class: 0,
-                score: 0.7,
-            },
-            Detection {
-                bbox: BBox::new(2.0, 3.0, 4.0, 4.0),
-                class: 0,
-                score: 0.8,
-            },
+            Detection::new(BBox::new(0.0, 0.0, 1.0, 1.0), 0).with_score(0.7),
+            Detection::new(BBox::new(2.0, 3.0, 4.0, 4.0), 0).with_score(0.8),
         ];
         let detection_indices = vec![0, 1];
 
-        let trackers = vec![KalmanBoxTracker::new(BBox::new(0.5, 0.0, 1.5, 1.0), 0, 3)];
+        let trackers = vec![KalmanBoxTracker::new(
+            BBox::new(0.5, 0.0, 1.5, 1.0),
+            0,
+            3,
+            0,
+        )];
         let tracker_indices = vec![0];
 
-        let iou_threshold = 0.3;
+        let config = OCSortConfig::default();
 
         let (matched_indices, unmatched_detection_indices, unmatched_tracker_indices) =
             associate_detections_to_trackers(
@@ -315,11 +1371,1409 @@ mod tests {
                 &detection_indices,
                 &trackers,
                 &tracker_indices,
-                iou_threshold,
+                &config,
+                &HungarianMatcher,
             );
 
         assert_eq!(matched_indices, vec![(0, 0)]);
         assert_eq!(unmatched_detection_indices, vec![1]);
         assert_eq!(unmatched_tracker_indices, Vec::<usize>::new());
     }
+
+    #[test]
+    fn test_associate_detections_to_trackers_with_iou_reports_iou_of_matches() {
+        let detections: Vec<Detection> = [
+            BBox::new(100.0, 0.0, 110.0, 10.0),
+            BBox::new(2.0, 1.0, 12.0, 11.0),
+        ]
+        .into_iter()
+        .map(|bbox| Detection::new(bbox, 0))
+        .collect();
+        let tracker_bbox = BBox::new(0.0, 0.0, 10.0, 10.0);
+        let trackers = vec![KalmanBoxTracker::new(tracker_bbox, 0, 3, 0)];
+        let config = OCSortConfig::default();
+
+        let (matched, unmatched_detections, unmatched_trackers) =
+            associate_detections_to_trackers_with_iou(
+                &detections,
+                &[0, 1],
+                &trackers,
+                &[0],
+                &config,
+                &HungarianMatcher,
+            );
+
+        assert_eq!(matched.len(), 1);
+        let (detection_index, tracker_index, iou) = matched[0];
+        assert_eq!((detection_index, tracker_index), (1, 0));
+        assert_eq!(iou, detections[1].bbox.iou(&trackers[0].get_bbox()));
+        assert_eq!(unmatched_detections, vec![0]);
+        assert!(unmatched_trackers.is_empty());
+    }
+
+    #[test]
+    fn test_associate_detections_to_trackers_with_iou_reports_mask_iou_of_matches() {
+        let bbox = BBox::new(0.0, 0.0, 2.0, 2.0);
+        let detection_mask = RleMask::from_bitmap(2, 2, &[true, true, false, true]);
+        let tracker_mask = RleMask::from_bitmap(2, 2, &[true, false, true, false]);
+        let detections = vec![Detection::new(bbox, 0).with_mask(detection_mask.clone())];
+        let mut trackers = vec![KalmanBoxTracker::new(bbox, 0, 3, 0)];
+        trackers[0].mask = Some(tracker_mask.clone());
+        let config = OCSortConfig {
+            iou_threshold: 0.2,
+            ..Default::default()
+        };
+
+        let (matched, _, _) = associate_detections_to_trackers_with_iou(
+            &detections,
+            &[0],
+            &trackers,
+            &[0],
+            &config,
+            &HungarianMatcher,
+        );
+
+        assert_eq!(
+            matched,
+            vec![(0, 0, mask_iou(&detection_mask, &tracker_mask))]
+        );
+    }
+
+    #[test]
+    fn test_build_cost_matrix_composes_iou_and_class_costs() {
+        let detections = vec![
+            Detection::new(BBox::new(2.0, 1.0, 12.0, 11.0), 0),
+            Detection::new(BBox::new(0.0, 0.0, 10.0, 10.0), 1),
+        ];
+        let tracker_bbox = BBox::new(0.0, 0.0, 10.0, 10.0);
+        // A new tracker has no speed direction, so there is no momentum cost.
+        let trackers = vec![KalmanBoxTracker::new(tracker_bbox, 0, 3, 0)];
+        let config = OCSortConfig::default();
+
+        let cost_matrix = build_cost_matrix(&detections, &[0, 1], &trackers, &[0], &config);
+
+        assert_eq!((cost_matrix.rows(), cost_matrix.columns()), (2, 1));
+        let iou = detections[0].bbox.iou(&tracker_bbox);
+        assert_eq!(cost_matrix[(0, 0)], (-iou * config.cost_multiplier) as i64);
+        // Identical boxes with mismatching classes get the class mismatch penalty of 100.
+        assert_eq!(
+            cost_matrix[(1, 0)],
+            ((-1.0 + 100.0) * config.cost_multiplier) as i64
+        );
+    }
+
+    #[test]
+    fn test_mask_iou_replaces_box_iou_when_both_have_masks() {
+        // Two L-shaped objects in the same box, overlapping in a single pixel of four.
+        let bbox = BBox::new(0.0, 0.0, 2.0, 2.0);
+        let detection_mask = RleMask::from_bitmap(2, 2, &[true, true, false, true]);
+        let tracker_mask = RleMask::from_bitmap(2, 2, &[true, false, true, false]);
+        let detections = vec![
+            Detection::new(bbox, 0).with_mask(detection_mask.clone()),
+            Detection::new(bbox, 0),
+        ];
+        let mut trackers = vec![KalmanBoxTracker::new(bbox, 0, 3, 0)];
+        trackers[0].mask = Some(tracker_mask.clone());
+        let config = OCSortConfig::default();
+
+        let cost_matrix = build_cost_matrix(&detections, &[0, 1], &trackers, &[0], &config);
+
+        let mask_iou = mask_iou(&detection_mask, &tracker_mask);
+        assert!((mask_iou - 0.25).abs() < 1e-9);
+        assert_ne!(mask_iou, bbox.iou(&bbox));
+        assert_eq!(
+            cost_matrix[(0, 0)],
+            (-mask_iou * config.cost_multiplier) as i64
+        );
+        // The detection without a mask falls back to the box IoU.
+        assert_eq!(
+            cost_matrix[(1, 0)],
+            (-bbox.iou(&bbox) * config.cost_multiplier) as i64
+        );
+    }
+
+    #[test]
+    fn test_mask_iou_is_on_the_scale_of_the_association_metric() {
+        let bbox = BBox::new(0.0, 0.0, 2.0, 2.0);
+        let detection_mask = RleMask::from_bitmap(2, 2, &[true, true, false, true]);
+        let tracker_mask = RleMask::from_bitmap(2, 2, &[true, false, true, false]);
+        let detections = vec![Detection::new(bbox, 0).with_mask(detection_mask.clone())];
+        let mut trackers = vec![KalmanBoxTracker::new(bbox, 0, 3, 0)];
+        trackers[0].mask = Some(tracker_mask.clone());
+        let config = OCSortConfig {
+            association_metric: AssociationMetric::Giou,
+            ..Default::default()
+        };
+
+        let cost_matrix = build_cost_matrix(&detections, &[0], &trackers, &[0], &config);
+
+        let similarity = (mask_iou(&detection_mask, &tracker_mask) + 1.0) / 2.0;
+        assert_eq!(
+            cost_matrix[(0, 0)],
+            (-similarity * config.cost_multiplier) as i64
+        );
+    }
+
+    #[test]
+    fn test_stale_tracker_mask_is_only_used_in_ocr_stage() {
+        // The masks overlap in a single pixel of four, far below the IoU threshold, while
+        // the boxes are identical.
+        let bbox = BBox::new(0.0, 0.0, 2.0, 2.0);
+        let detections = vec![
+            Detection::new(bbox, 0)
+                .with_score(0.9)
+                .with_mask(RleMask::from_bitmap(2, 2, &[true, true, false, true])),
+        ];
+        let mut trackers = vec![KalmanBoxTracker::new(bbox, 0, 3, 0)];
+        trackers[0].mask = Some(RleMask::from_bitmap(2, 2, &[true, false, true, false]));
+        for _ in 0..3 {
+            trackers[0].predict();
+        }
+        let config = OCSortConfig::default();
+
+        let cost_matrix = build_cost_matrix(&detections, &[0], &trackers, &[0], &config);
+        assert_eq!(
+            cost_matrix[(0, 0)],
+            (-bbox.iou(&trackers[0].get_bbox()) * config.cost_multiplier) as i64
+        );
+
+        let (matched_indices, _, _) = observation_centric_recovery(
+            &detections,
+            &[0],
+            &trackers,
+            &[0],
+            &config,
+            &HungarianMatcher,
+        );
+        assert!(matched_indices.is_empty());
+    }
+
+    #[test]
+    fn test_associate_detections_to_trackers_with_giou_matches_non_overlapping_boxes_below_zero_threshold()
+     {
+        let detections = vec![Detection::new(BBox::new(1.1, 0.0, 2.1, 1.0), 0).with_score(0.7)];
+        let trackers = vec![KalmanBoxTracker::new(
+            BBox::new(0.0, 0.0, 1.0, 1.0),
+            0,
+            3,
+            0,
+        )];
+
+        let iou_config = OCSortConfig::default();
+        let (matched_indices, _, _) = associate_detections_to_trackers(
+            &detections,
+            &[0],
+            &trackers,
+            &[0],
+            &iou_config,
+            &HungarianMatcher,
+        );
+        assert!(matched_indices.is_empty());
+
+        let giou_config = OCSortConfig {
+            association_metric: AssociationMetric::Giou,
+            ..Default::default()
+        };
+        let (matched_indices, _, _) = associate_detections_to_trackers(
+            &detections,
+            &[0],
+            &trackers,
+            &[0],
+            &giou_config,
+            &HungarianMatcher,
+        );
+        assert!(matched_indices.is_empty());
+
+        let giou_config = OCSortConfig {
+            iou_threshold: -0.1,
+            ..giou_config
+        };
+        let (matched_indices, _, _) = associate_detections_to_trackers(
+            &detections,
+            &[0],
+            &trackers,
+            &[0],
+            &giou_config,
+            &HungarianMatcher,
+        );
+        assert_eq!(matched_indices, vec![(0, 0)]);
+    }
+
+    #[test]
+    fn test_giou_diou_and_ciou_leave_far_apart_boxes_unmatched_with_default_threshold() {
+        let detections = vec![Detection::new(BBox::new(5.0, 0.0, 6.0, 1.0), 0).with_score(0.7)];
+        let trackers = vec![KalmanBoxTracker::new(
+            BBox::new(0.0, 0.0, 1.0, 1.0),
+            0,
+            3,
+            0,
+        )];
+
+        for association_metric in [
+            AssociationMetric::Giou,
+            AssociationMetric::Diou,
+            AssociationMetric::Ciou,
+        ] {
+            let config = OCSortConfig {
+                association_metric,
+                ..Default::default()
+            };
+            let (matched_indices, unmatched_detection_indices, unmatched_tracker_indices) =
+                associate_detections_to_trackers(
+                    &detections,
+                    &[0],
+                    &trackers,
+                    &[0],
+                    &config,
+                    &HungarianMatcher,
+                );
+
+            assert!(matched_indices.is_empty(), "{association_metric:?}");
+            assert_eq!(unmatched_detection_indices, vec![0]);
+            assert_eq!(unmatched_tracker_indices, vec![0]);
+        }
+    }
+
+    #[test]
+    fn test_associate_detections_to_trackers_with_diou_prefers_closer_detection() {
+        let detections = vec![
+            Detection::new(BBox::new(3.0, 0.0, 4.0, 1.0), 0).with_score(0.7),
+            Detection::new(BBox::new(1.5, 0.0, 2.5, 1.0), 0).with_score(0.7),
+        ];
+        let trackers = vec![KalmanBoxTracker::new(
+            BBox::new(0.0, 0.0, 1.0, 1.0),
+            0,
+            3,
+            0,
+        )];
+        // Both detections pass the threshold, the DIoU of the farther one is about -0.53.
+        let config = OCSortConfig {
+            iou_threshold: -0.6,
+            association_metric: AssociationMetric::Diou,
+            ..Default::default()
+        };
+
+        let (matched_indices, unmatched_detection_indices, _) = associate_detections_to_trackers(
+            &detections,
+            &[0, 1],
+            &trackers,
+            &[0],
+            &config,
+            &HungarianMatcher,
+        );
+
+        assert_eq!(matched_indices, vec![(1, 0)]);
+        assert_eq!(unmatched_detection_indices, vec![0]);
+    }
+
+    #[test]
+    fn test_inertia_weight_changes_matching_of_ambiguous_detections() {
+        let mut tracker = KalmanBoxTracker::new(BBox::new(0.0, 0.0, 10.0, 10.0), 0, 3, 0);
+        tracker.predict();
+        tracker.update(
+            &Detection::new(BBox::new(0.1, 0.0, 10.1, 10.0), 0).with_score(0.7),
+            &OCSortConfig::default(),
+        );
+        tracker.predict();
+        let trackers = vec![tracker];
+
+        let detections = vec![
+            // Higher iou but moving against the direction of the tracker.
+            Detection::new(BBox::new(-0.3, 0.0, 9.7, 10.0), 0).with_score(0.7),
+            // Lower iou but moving along the direction of the tracker.
+            Detection::new(BBox::new(2.5, 0.0, 12.5, 10.0), 0).with_score(0.7),
+        ];
+
+        let config = OCSortConfig::default();
+        let (matched_indices, _, _) = associate_detections_to_trackers(
+            &detections,
+            &[0, 1],
+            &trackers,
+            &[0],
+            &config,
+            &HungarianMatcher,
+        );
+        assert_eq!(matched_indices, vec![(0, 0)]);
+
+        let config = OCSortConfig {
+            inertia_weight: 0.5,
+            ..Default::default()
+        };
+        let (matched_indices, _, _) = associate_detections_to_trackers(
+            &detections,
+            &[0, 1],
+            &trackers,
+            &[0],
+            &config,
+            &HungarianMatcher,
+        );
+        assert_eq!(matched_indices, vec![(1, 0)]);
+    }
+
+    #[test]
+    fn test_greedy_and_hungarian_agree_on_separable_case() {
+        let bboxes = [
+            BBox::new(0.0, 0.0, 1.0, 1.0),
+            BBox::new(5.0, 5.0, 6.0, 6.0),
+            BBox::new(10.0, 0.0, 11.0, 1.0),
+        ];
+        let detections: Vec<Detection> = bboxes
+            .iter()
+            .rev()
+            .map(|&bbox| Detection::new(bbox, 0).with_score(0.9))
+            .collect();
+        let trackers: Vec<KalmanBoxTracker> = bboxes
+            .iter()
+            .enumerate()
+            .map(|(i, &bbox)| KalmanBoxTracker::new(bbox, 0, 3, i as u32))
+            .collect();
+
+        let hungarian_config = OCSortConfig::default();
+        let greedy_config = OCSortConfig {
+            matching_strategy: MatchingStrategy::Greedy,
+            ..Default::default()
+        };
+
+        let hungarian = associate_detections_to_trackers(
+            &detections,
+            &[0, 1, 2],
+            &trackers,
+            &[0, 1, 2],
+            &hungarian_config,
+            &HungarianMatcher,
+        );
+        let greedy = associate_detections_to_trackers(
+            &detections,
+            &[0, 1, 2],
+            &trackers,
+            &[0, 1, 2],
+            &greedy_config,
+            &HungarianMatcher,
+        );
+
+        assert_eq!(hungarian.0, vec![(0, 2), (1, 1), (2, 0)]);
+        assert_eq!(greedy, hungarian);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_parallel_cost_matrices_equal_serial_cost_matrices() {
+        let detections: Vec<Detection> = (0..80)
+            .map(|i| {
+                let x = (i % 10) as f64 * 7.3;
+                let y = (i / 10) as f64 * 6.1;
+                Detection::new(BBox::new(x, y, x + 9.0, y + 8.0), i % 3).with_score(0.9)
+            })
+            .collect();
+        let trackers: Vec<KalmanBoxTracker> = (0..80)
+            .map(|i| {
+                let x = (i % 10) as f64 * 7.0 + 1.0;
+                let y = (i / 10) as f64 * 6.0 + 0.5;
+                let mut tracker =
+                    KalmanBoxTracker::new(BBox::new(x, y, x + 9.0, y + 8.0), i % 3, 3, i);
+                tracker.predict();
+                tracker.update(
+                    &Detection::new(BBox::new(x + 0.7, y + 0.2, x + 9.7, y + 8.2), i % 3)
+                        .with_score(0.9),
+                    &OCSortConfig::default(),
+                );
+                tracker.predict();
+                tracker
+            })
+            .collect();
+        let tracker_indices: Vec<usize> = (0..trackers.len()).collect();
+
+        let cost_matrices = |detection_indices: &[usize]| {
+            let mut buffers = AssociationBuffers::default();
+            let (detection_bboxes, tracker_bboxes) = get_bboxes(
+                &detections,
+                detection_indices,
+                &trackers,
+                &tracker_indices,
+                &mut buffers,
+            );
+            let iou_matrix = calc_iou_cost_matrix(
+                detection_bboxes,
+                tracker_bboxes,
+                AssociationMetric::Ciou,
+                10000.0,
+            );
+            let mut cost_matrix = iou_matrix.clone();
+            add_speed_cost_matrix(
+                detection_bboxes,
+                &trackers,
+                &tracker_indices,
+                0.2,
+                10000.0,
+                &mut cost_matrix,
+            );
+            add_class_cost_matrix(
+                &detections,
+                detection_indices,
+                &trackers,
+                &tracker_indices,
+                false,
+                10000.0,
+                &mut cost_matrix,
+            );
+            (iou_matrix, cost_matrix)
+        };
+
+        // The full matrices are above the threshold and a single row is below it.
+        let detection_indices: Vec<usize> = (0..detections.len()).collect();
+        assert!(detection_indices.len() * tracker_indices.len() >= PARALLEL_THRESHOLD);
+        assert!(tracker_indices.len() < PARALLEL_THRESHOLD);
+        let (parallel_iou_matrix, parallel_cost_matrix) = cost_matrices(&detection_indices);
+
+        for &detection_index in &detection_indices {
+            let (serial_iou_matrix, serial_cost_matrix) = cost_matrices(&[detection_index]);
+            for j in 0..tracker_indices.len() {
+                assert_eq!(
+                    parallel_iou_matrix[(detection_index, j)],
+                    serial_iou_matrix[(0, j)]
+                );
+                assert_eq!(
+                    parallel_cost_matrix[(detection_index, j)],
+                    serial_cost_matrix[(0, j)]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_area_ratio_weight_prefers_similarly_sized_detection() {
+        let trackers = vec![KalmanBoxTracker::new(
+            BBox::new(0.0, 0.0, 10.0, 10.0),
+            0,
+            3,
+            0,
+        )];
+        let detections = vec![
+            // Same size as the tracker.
+            Detection::new(BBox::new(2.0, 0.0, 12.0, 10.0), 0).with_score(0.7),
+            // Slightly higher iou but a third smaller.
+            Detection::new(BBox::new(0.0, 0.0, 10.0, 6.8), 0).with_score(0.7),
+        ];
+
+        let config = OCSortConfig::default();
+        let (matched_indices, _, _) = associate_detections_to_trackers(
+            &detections,
+            &[0, 1],
+            &trackers,
+            &[0],
+            &config,
+            &HungarianMatcher,
+        );
+        assert_eq!(matched_indices, vec![(1, 0)]);
+
+        let config = OCSortConfig {
+            area_ratio_weight: 0.5,
+            ..Default::default()
+        };
+        let (matched_indices, _, _) = associate_detections_to_trackers(
+            &detections,
+            &[0, 1],
+            &trackers,
+            &[0],
+            &config,
+            &HungarianMatcher,
+        );
+        assert_eq!(matched_indices, vec![(0, 0)]);
+    }
+
+    #[test]
+    fn test_class_gating_rejects_only_candidate_of_other_class() {
+        let trackers = vec![KalmanBoxTracker::new(
+            BBox::new(0.0, 0.0, 10.0, 10.0),
+            5,
+            3,
+            0,
+        )];
+        let detections = vec![Detection::new(BBox::new(0.0, 0.0, 10.0, 10.0), 2).with_score(0.9)];
+        let config = OCSortConfig {
+            class_gating: true,
+            ..Default::default()
+        };
+
+        for (matched_indices, unmatched_detections, unmatched_trackers) in [
+            associate_detections_to_trackers(
+                &detections,
+                &[0],
+                &trackers,
+                &[0],
+                &config,
+                &HungarianMatcher,
+            ),
+            byte_associate(
+                &detections,
+                &[0],
+                &trackers,
+                &[0],
+                &config,
+                &HungarianMatcher,
+            ),
+            observation_centric_recovery(
+                &detections,
+                &[0],
+                &trackers,
+                &[0],
+                &config,
+                &HungarianMatcher,
+            ),
+        ] {
+            assert!(matched_indices.is_empty());
+            assert_eq!(unmatched_detections, vec![0]);
+            assert_eq!(unmatched_trackers, vec![0]);
+        }
+
+        // A gated pair next to a valid one.
+        let trackers = vec![
+            KalmanBoxTracker::new(BBox::new(0.0, 0.0, 10.0, 10.0), 5, 3, 0),
+            KalmanBoxTracker::new(BBox::new(20.0, 0.0, 30.0, 10.0), 2, 3, 1),
+        ];
+        let (matched_indices, unmatched_detections, unmatched_trackers) =
+            associate_detections_to_trackers(
+                &detections,
+                &[0],
+                &trackers,
+                &[0, 1],
+                &config,
+                &HungarianMatcher,
+            );
+        assert!(matched_indices.is_empty());
+        assert_eq!(unmatched_detections, vec![0]);
+        assert_eq!(unmatched_trackers, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_class_agnostic_matches_across_classes() {
+        let trackers = vec![KalmanBoxTracker::new(
+            BBox::new(0.0, 0.0, 10.0, 10.0),
+            5,
+            3,
+            0,
+        )];
+        let detections = vec![Detection::new(BBox::new(0.0, 0.0, 10.0, 10.0), 2).with_score(0.9)];
+        let config = OCSortConfig {
+            class_agnostic: true,
+            ..Default::default()
+        };
+
+        let (matched_indices, _, _) = associate_detections_to_trackers(
+            &detections,
+            &[0],
+            &trackers,
+            &[0],
+            &config,
+            &HungarianMatcher,
+        );
+        assert_eq!(matched_indices, vec![(0, 0)]);
+    }
+
+    #[test]
+    fn test_ext_id_resolves_iou_tie() {
+        let mut trackers: Vec<KalmanBoxTracker> = (0..2)
+            .map(|id| KalmanBoxTracker::new(BBox::new(0.0, 0.0, 10.0, 10.0), 0, 3, id))
+            .collect();
+        trackers[0].ext_id = Some(7);
+        trackers[1].ext_id = Some(8);
+        let detections = vec![
+            Detection::new(BBox::new(0.0, 0.0, 10.0, 10.0), 0)
+                .with_score(0.9)
+                .with_ext_id(8),
+        ];
+
+        let (matched_indices, _, _) = associate_detections_to_trackers(
+            &detections,
+            &[0],
+            &trackers,
+            &[0, 1],
+            &OCSortConfig::default(),
+            &HungarianMatcher,
+        );
+        assert_eq!(matched_indices, vec![(0, 0)]);
+
+        let config = OCSortConfig {
+            use_ext_ids: true,
+            ..Default::default()
+        };
+        let (matched_indices, _, unmatched_trackers) = associate_detections_to_trackers(
+            &detections,
+            &[0],
+            &trackers,
+            &[0, 1],
+            &config,
+            &HungarianMatcher,
+        );
+        assert_eq!(matched_indices, vec![(0, 1)]);
+        assert_eq!(unmatched_trackers, vec![0]);
+    }
+
+    #[test]
+    fn test_embeddings_disambiguate_crossing_objects() {
+        let bbox = BBox::new(0.0, 0.0, 10.0, 10.0);
+        let mut trackers: Vec<KalmanBoxTracker> = (0..2)
+            .map(|id| KalmanBoxTracker::new(bbox, 0, 3, id))
+            .collect();
+        trackers[0].embedding = Some(vec![1.0, 0.0]);
+        trackers[1].embedding = Some(vec![0.0, 1.0]);
+        let detections: Vec<Detection> = [vec![0.0, 1.0], vec![1.0, 0.0]]
+            .into_iter()
+            .map(|embedding| {
+                Detection::new(bbox, 0)
+                    .with_score(0.9)
+                    .with_embedding(embedding)
+            })
+            .collect();
+
+        let (matched_indices, _, _) = associate_detections_to_trackers(
+            &detections,
+            &[0, 1],
+            &trackers,
+            &[0, 1],
+            &OCSortConfig::default(),
+            &HungarianMatcher,
+        );
+        assert_eq!(matched_indices, vec![(0, 0), (1, 1)]);
+
+        let config = OCSortConfig {
+            appearance_weight: 0.5,
+            ..Default::default()
+        };
+        let (matched_indices, _, _) = associate_detections_to_trackers(
+            &detections,
+            &[0, 1],
+            &trackers,
+            &[0, 1],
+            &config,
+            &HungarianMatcher,
+        );
+        assert_eq!(matched_indices, vec![(0, 1), (1, 0)]);
+    }
+
+    /// Matcher assigning row i to column i regardless of the costs.
+    struct IdentityMatcher;
+
+    impl Matcher for IdentityMatcher {
+        fn solve(&self, cost_matrix: &CostMatrix) -> Vec<(usize, usize)> {
+            (0..cost_matrix.rows().min(cost_matrix.columns()))
+                .map(|i| (i, i))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_custom_matcher_replaces_hungarian_solver() {
+        let trackers: Vec<KalmanBoxTracker> = [0.0, 2.0]
+            .into_iter()
+            .enumerate()
+            .map(|(id, x)| {
+                KalmanBoxTracker::new(BBox::new(x, 0.0, x + 10.0, 10.0), 0, 3, id as u32)
+            })
+            .collect();
+        let detections: Vec<Detection> = [2.0, 0.0]
+            .into_iter()
+            .map(|x| Detection::new(BBox::new(x, 0.0, x + 10.0, 10.0), 0).with_score(0.9))
+            .collect();
+        let config = OCSortConfig::default();
+        let run = |matcher: &dyn Matcher| {
+            associate_detections_to_trackers(
+                &detections,
+                &[0, 1],
+                &trackers,
+                &[0, 1],
+                &config,
+                matcher,
+            )
+            .0
+        };
+
+        assert_eq!(run(&HungarianMatcher), vec![(0, 1), (1, 0)]);
+        assert_eq!(run(&IdentityMatcher), vec![(0, 0), (1, 1)]);
+    }
+
+    struct InvalidPairsMatcher;
+
+    impl Matcher for InvalidPairsMatcher {
+        fn solve(&self, _cost_matrix: &CostMatrix) -> Vec<(usize, usize)> {
+            vec![(0, 0), (0, 1), (5, 0), (1, 7), (1, 0), (1, 1)]
+        }
+    }
+
+    #[test]
+    fn test_matcher_pairs_out_of_range_or_repeated_are_ignored() {
+        let trackers: Vec<KalmanBoxTracker> = [0.0, 2.0]
+            .into_iter()
+            .enumerate()
+            .map(|(id, x)| {
+                KalmanBoxTracker::new(BBox::new(x, 0.0, x + 10.0, 10.0), 0, 3, id as u32)
+            })
+            .collect();
+        let detections: Vec<Detection> = [2.0, 0.0]
+            .into_iter()
+            .map(|x| Detection::new(BBox::new(x, 0.0, x + 10.0, 10.0), 0).with_score(0.9))
+            .collect();
+
+        let result = associate_detections_to_trackers(
+            &detections,
+            &[0, 1],
+            &trackers,
+            &[0, 1],
+            &OCSortConfig::default(),
+            &InvalidPairsMatcher,
+        );
+
+        assert_eq!(result.0, vec![(0, 0), (1, 1)]);
+        assert_partition(&result, &[0, 1], &[0, 1]);
+    }
+
+    #[test]
+    fn test_cost_matrix_from_rows_rejects_ragged_rows() {
+        let cost_matrix = CostMatrix::from_rows(vec![vec![1, 2, 3], vec![4, 5, 6]]).unwrap();
+
+        assert_eq!((cost_matrix.rows(), cost_matrix.columns()), (2, 3));
+        assert_eq!(cost_matrix.row(1), &[4, 5, 6]);
+        assert_eq!(cost_matrix[(0, 2)], 3);
+        assert!(CostMatrix::from_rows(vec![vec![1, 2], vec![3]]).is_none());
+    }
+
+    /// Small linear congruential generator so the property tests are reproducible
+    /// without extra dependencies.
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next_f64(&mut self) -> f64 {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (self.0 >> 11) as f64 / (1u64 << 53) as f64
+        }
+
+        fn next_bbox(&mut self) -> BBox {
+            let x = self.next_f64() * 30.0;
+            let y = self.next_f64() * 30.0;
+            BBox::new(
+                x,
+                y,
+                x + 5.0 + self.next_f64() * 10.0,
+                y + 5.0 + self.next_f64() * 10.0,
+            )
+        }
+    }
+
+    /// Asserts that every index appears exactly once across the matched and unmatched outputs.
+    fn assert_partition(
+        (matched_indices, unmatched_detections, unmatched_trackers): &(
+            Vec<(usize, usize)>,
+            Vec<usize>,
+            Vec<usize>,
+        ),
+        detection_indices: &[usize],
+        tracker_indices: &[usize],
+    ) {
+        let mut detections: Vec<usize> = matched_indices
+            .iter()
+            .map(|&(detection_index, _)| detection_index)
+            .chain(unmatched_detections.iter().copied())
+            .collect();
+        let mut trackers: Vec<usize> = matched_indices
+            .iter()
+            .map(|&(_, tracker_index)| tracker_index)
+            .chain(unmatched_trackers.iter().copied())
+            .collect();
+        detections.sort_unstable();
+        trackers.sort_unstable();
+
+        let mut expected_detections = detection_indices.to_vec();
+        let mut expected_trackers = tracker_indices.to_vec();
+        expected_detections.sort_unstable();
+        expected_trackers.sort_unstable();
+        assert_eq!(detections, expected_detections);
+        assert_eq!(trackers, expected_trackers);
+    }
+
+    #[test]
+    fn test_hungarian_assignment_rectangular_matrices() {
+        let mut rng = Lcg(7);
+        for rows in 1..7 {
+            for columns in 1..7 {
+                let mut cost_matrix = CostMatrix::new(rows, columns, 0);
+                for cell in cost_matrix.0.iter_mut() {
+                    *cell = (rng.next_f64() * 1000.0) as i64 - 500;
+                }
+
+                let assignment = hungarian_assignment(&cost_matrix);
+
+                assert_eq!(assignment.len(), rows.min(columns));
+                assert!(assignment.iter().map(|&(row, _)| row).all_unique());
+                assert!(assignment.iter().map(|&(_, column)| column).all_unique());
+                assert!(
+                    assignment
+                        .iter()
+                        .all(|&(row, column)| row < rows && column < columns)
+                );
+            }
+        }
+    }
+
+    fn tie_broken_hungarian_assignment(cost_matrix: &CostMatrix) -> Vec<(usize, usize)> {
+        let mut cost_matrix = cost_matrix.clone();
+        break_ties(&mut cost_matrix, |_, _| true);
+        hungarian_assignment(&cost_matrix)
+    }
+
+    #[test]
+    fn test_hungarian_assignment_breaks_ties_by_index() {
+        // Every assignment of a constant matrix has the same cost, so detection i is
+        // paired with tracker i.
+        let cost_matrix = CostMatrix::new(3, 3, 5);
+        assert_eq!(
+            tie_broken_hungarian_assignment(&cost_matrix),
+            vec![(0, 0), (1, 1), (2, 2)]
+        );
+
+        // The lower tracker wins a tie for a single detection and vice versa.
+        assert_eq!(
+            tie_broken_hungarian_assignment(&CostMatrix::new(1, 3, 5)),
+            vec![(0, 0)]
+        );
+        assert_eq!(
+            tie_broken_hungarian_assignment(&CostMatrix::new(3, 1, 5)),
+            vec![(0, 0)]
+        );
+
+        // The perturbation never makes the assignment worse: the crossing pairs of the
+        // first two rows are kept since they are cheaper than the diagonal.
+        let cost_matrix =
+            CostMatrix::from_rows(vec![vec![2, 1, 3], vec![1, 2, 3], vec![3, 3, 3]]).unwrap();
+        assert_eq!(
+            tie_broken_hungarian_assignment(&cost_matrix),
+            vec![(0, 1), (1, 0), (2, 2)]
+        );
+    }
+
+    #[test]
+    fn test_hungarian_assignment_breaks_three_way_tie_by_index() {
+        // The diagonal and both 3-cycles cost 0, every assignment swapping a single pair
+        // costs 9 or more, so no pairwise exchange turns a cycle into the diagonal.
+        let cost_matrix =
+            CostMatrix::from_rows(vec![vec![0, 0, 0], vec![9, 0, 0], vec![0, 9, 0]]).unwrap();
+
+        assert_eq!(
+            tie_broken_hungarian_assignment(&cost_matrix),
+            vec![(0, 0), (1, 1), (2, 2)]
+        );
+        // The rows and columns in reverse order still pair in index order.
+        let reversed = CostMatrix::from_rows(
+            (0..3)
+                .rev()
+                .map(|row| {
+                    (0..3)
+                        .rev()
+                        .map(|column| cost_matrix[(row, column)])
+                        .collect()
+                })
+                .collect(),
+        )
+        .unwrap();
+        assert_eq!(
+            tie_broken_hungarian_assignment(&reversed),
+            vec![(0, 0), (1, 1), (2, 2)]
+        );
+    }
+
+    #[test]
+    fn test_break_ties_prefers_pairs_that_pass_the_checks() {
+        let mut cost_matrix = CostMatrix::new(2, 2, 5);
+        break_ties(&mut cost_matrix, |row, column| row != column);
+        assert_eq!(hungarian_assignment(&cost_matrix), vec![(0, 1), (1, 0)]);
+
+        // Costs too large to scale are left as they are.
+        let mut cost_matrix = CostMatrix::new(2, 2, MAX_COST / 4);
+        cost_matrix[(0, 1)] = MAX_COST;
+        let unchanged = cost_matrix.clone();
+        break_ties(&mut cost_matrix, |_, _| true);
+        assert_eq!(cost_matrix, unchanged);
+    }
+
+    #[test]
+    fn test_equal_cost_tie_break_keeps_match_that_passes_iou_threshold() {
+        let detections = [Detection::new(BBox::new(0.0, 0.0, 10.0, 10.0), 0).with_score(0.9)];
+        let trackers: Vec<KalmanBoxTracker> = (0..2)
+            .map(|id| KalmanBoxTracker::new(detections[0].bbox, 0, 3, id))
+            .collect();
+        let config = OCSortConfig::default();
+        // Both pairs cost the same, but only the second tracker passes the IoU threshold.
+        let cost_matrix = CostMatrix::new(1, 2, -1000);
+        let iou_matrix = CostMatrix::from_rows(vec![vec![-1000, -5000]]).unwrap();
+
+        let (matched, unmatched_detections, unmatched_trackers) = calculate_matching(
+            &detections,
+            &[0],
+            &trackers,
+            &[0, 1],
+            cost_matrix,
+            &iou_matrix,
+            config.iou_threshold,
+            &config,
+            &HungarianMatcher,
+        );
+
+        assert_eq!(matched, vec![(0, 1)]);
+        assert!(unmatched_detections.is_empty());
+        assert_eq!(unmatched_trackers, vec![0]);
+    }
+
+    #[test]
+    fn test_association_outputs_partition_rectangular_inputs() {
+        let mut rng = Lcg(42);
+        let detections: Vec<Detection> = (0..12)
+            .map(|i| Detection::new(rng.next_bbox(), i % 2).with_score(0.9))
+            .collect();
+        let trackers: Vec<KalmanBoxTracker> = (0..12)
+            .map(|i| KalmanBoxTracker::new(rng.next_bbox(), i % 2, 3, i))
+            .collect();
+
+        for matching_strategy in [MatchingStrategy::Hungarian, MatchingStrategy::Greedy] {
+            let config = OCSortConfig {
+                matching_strategy,
+                ..Default::default()
+            };
+            for detection_count in 0..7 {
+                for tracker_count in 0..7 {
+                    // Offset and stride the indices so that local and global indices differ.
+                    let detection_indices: Vec<usize> =
+                        (0..detection_count).map(|i| 2 * i + 1).collect();
+                    let tracker_indices: Vec<usize> = (0..tracker_count).map(|i| 11 - i).collect();
+
+                    for result in [
+                        associate_detections_to_trackers(
+                            &detections,
+                            &detection_indices,
+                            &trackers,
+                            &tracker_indices,
+                            &config,
+                            &HungarianMatcher,
+                        ),
+                        byte_associate(
+                            &detections,
+                            &detection_indices,
+                            &trackers,
+                            &tracker_indices,
+                            &config,
+                            &HungarianMatcher,
+                        ),
+                        observation_centric_recovery(
+                            &detections,
+                            &detection_indices,
+                            &trackers,
+                            &tracker_indices,
+                            &config,
+                            &HungarianMatcher,
+                        ),
+                    ] {
+                        assert_partition(&result, &detection_indices, &tracker_indices);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rejected_match_does_not_duplicate_indices() {
+        let trackers = vec![
+            KalmanBoxTracker::new(BBox::new(0.0, 0.0, 10.0, 10.0), 0, 3, 0),
+            KalmanBoxTracker::new(BBox::new(100.0, 0.0, 110.0, 10.0), 0, 3, 1),
+        ];
+        // The solver assigns the far away detection to the second tracker,
+        // which is then rejected for a too low iou.
+        let detections = vec![
+            Detection::new(BBox::new(0.0, 0.0, 10.0, 10.0), 0).with_score(0.9),
+            Detection::new(BBox::new(50.0, 50.0, 60.0, 60.0), 0).with_score(0.9),
+        ];
+        let config = OCSortConfig::default();
+
+        let (matched_indices, unmatched_detections, unmatched_trackers) =
+            associate_detections_to_trackers(
+                &detections,
+                &[0, 1],
+                &trackers,
+                &[0, 1],
+                &config,
+                &HungarianMatcher,
+            );
+
+        assert_eq!(matched_indices, vec![(0, 0)]);
+        assert_eq!(unmatched_detections, vec![1]);
+        assert_eq!(unmatched_trackers, vec![1]);
+    }
+
+    #[test]
+    fn test_to_cost_saturates_non_finite_and_extreme_values() {
+        assert_eq!(to_cost(f64::NAN, 10000.0), MAX_COST);
+        assert_eq!(to_cost(f64::INFINITY, 10000.0), MAX_COST);
+        assert_eq!(to_cost(f64::NEG_INFINITY, 10000.0), MAX_COST);
+        assert_eq!(to_cost(1e300, 10000.0), MAX_COST);
+        assert_eq!(to_cost(-1e300, 10000.0), -MAX_COST);
+        assert_eq!(to_cost(-0.5, 10000.0), -5000);
+
+        let mut cell = MAX_COST;
+        add_cost(&mut cell, 1e300, 10000.0);
+        assert_eq!(cell, MAX_COST);
+    }
+
+    #[test]
+    fn test_degenerate_boxes_do_not_break_matching() {
+        let degenerate_bboxes = [
+            BBox {
+                x_1: f64::NAN,
+                y_1: 0.0,
+                x_2: 10.0,
+                y_2: 10.0,
+            },
+            BBox {
+                x_1: 0.0,
+                y_1: 0.0,
+                x_2: f64::INFINITY,
+                y_2: 10.0,
+            },
+            BBox::new(-1e300, -1e300, 1e300, 1e300),
+            BBox::new(5.0, 5.0, 5.0, 5.0),
+        ];
+        let trackers = vec![
+            KalmanBoxTracker::new(BBox::new(0.0, 0.0, 10.0, 10.0), 0, 3, 0),
+            KalmanBoxTracker::new(BBox::new(100.0, 0.0, 110.0, 10.0), 0, 3, 1),
+        ];
+
+        for matching_strategy in [MatchingStrategy::Hungarian, MatchingStrategy::Greedy] {
+            let config = OCSortConfig {
+                matching_strategy,
+                area_ratio_weight: 1.0,
+                ..Default::default()
+            };
+            for degenerate_bbox in degenerate_bboxes {
+                let detections = vec![
+                    Detection::new(degenerate_bbox, 0).with_score(0.9),
+                    Detection::new(BBox::new(100.0, 0.0, 110.0, 10.0), 0).with_score(0.9),
+                ];
+
+                let (matched_indices, unmatched_detections, unmatched_trackers) =
+                    associate_detections_to_trackers(
+                        &detections,
+                        &[0, 1],
+                        &trackers,
+                        &[0, 1],
+                        &config,
+                        &HungarianMatcher,
+                    );
+
+                assert!(matched_indices.contains(&(1, 1)));
+                assert_eq!(
+                    matched_indices.len() + unmatched_detections.len(),
+                    detections.len()
+                );
+                assert_eq!(
+                    matched_indices.len() + unmatched_trackers.len(),
+                    trackers.len()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_byte_iou_threshold_controls_byte_stage() {
+        let trackers = vec![KalmanBoxTracker::new(
+            BBox::new(0.0, 0.0, 10.0, 10.0),
+            0,
+            3,
+            0,
+        )];
+        // IoU of 0.25 with the tracker.
+        let detections = vec![Detection::new(BBox::new(0.0, 0.0, 10.0, 2.5), 0).with_score(0.2)];
+
+        let config = OCSortConfig::default();
+        let (matched_indices, _, _) = byte_associate(
+            &detections,
+            &[0],
+            &trackers,
+            &[0],
+            &config,
+            &HungarianMatcher,
+        );
+        assert!(matched_indices.is_empty());
+
+        let config = OCSortConfig {
+            byte_iou_threshold: Some(0.2),
+            ..Default::default()
+        };
+        let (matched_indices, _, _) = byte_associate(
+            &detections,
+            &[0],
+            &trackers,
+            &[0],
+            &config,
+            &HungarianMatcher,
+        );
+        assert_eq!(matched_indices, vec![(0, 0)]);
+        // The primary stage still uses iou_threshold.
+        let (matched_indices, _, _) = associate_detections_to_trackers(
+            &detections,
+            &[0],
+            &trackers,
+            &[0],
+            &config,
+            &HungarianMatcher,
+        );
+        assert!(matched_indices.is_empty());
+    }
+
+    #[test]
+    fn test_cost_multiplier_separates_nearly_equal_ious() {
+        let trackers = vec![
+            // IoU of ~0.99992 with the detection.
+            KalmanBoxTracker::new(BBox::new(0.0, 0.0, 100.0, 100.008), 0, 3, 0),
+            // IoU of ~0.99998 with the detection.
+            KalmanBoxTracker::new(BBox::new(0.0, 0.0, 100.0, 100.002), 0, 3, 1),
+        ];
+        let detections = vec![Detection::new(BBox::new(0.0, 0.0, 100.0, 100.0), 0).with_score(0.2)];
+
+        // Both IoUs round to the same cost and the tie goes to the first tracker.
+        let config = OCSortConfig::default();
+        let (matched_indices, _, _) = byte_associate(
+            &detections,
+            &[0],
+            &trackers,
+            &[0, 1],
+            &config,
+            &HungarianMatcher,
+        );
+        assert_eq!(matched_indices, vec![(0, 0)]);
+
+        let config = OCSortConfig {
+            cost_multiplier: 1e8,
+            ..Default::default()
+        };
+        let (matched_indices, _, _) = byte_associate(
+            &detections,
+            &[0],
+            &trackers,
+            &[0, 1],
+            &config,
+            &HungarianMatcher,
+        );
+        assert_eq!(matched_indices, vec![(0, 1)]);
+    }
+
+    #[test]
+    fn test_speed_cost_is_skipped_for_new_trackers() {
+        let config = OCSortConfig::default();
+        let mut trackers = vec![KalmanBoxTracker::new(
+            BBox::new(0.0, 0.0, 10.0, 10.0),
+            0,
+            3,
+            0,
+        )];
+        let detection_bboxes = [BBox::new(5.0, 0.0, 15.0, 10.0)];
+
+        let mut cost_matrix = CostMatrix::new(1, 1, 0);
+        add_speed_cost_matrix(
+            &detection_bboxes,
+            &trackers,
+            &[0],
+            0.2,
+            10000.0,
+            &mut cost_matrix,
+        );
+        assert!(!trackers[0].has_speed_direction());
+        assert_eq!(cost_matrix[(0, 0)], 0);
+
+        trackers[0].predict();
+        trackers[0].update(
+            &Detection::new(BBox::new(2.0, 0.0, 12.0, 10.0), 0).with_score(0.9),
+            &config,
+        );
+        let mut cost_matrix = CostMatrix::new(1, 1, 0);
+        add_speed_cost_matrix(
+            &detection_bboxes,
+            &trackers,
+            &[0],
+            0.2,
+            10000.0,
+            &mut cost_matrix,
+        );
+        // The detection continues in the direction of the tracker.
+        assert!(trackers[0].has_speed_direction());
+        assert_eq!(cost_matrix[(0, 0)], -2000);
+    }
+
+    #[test]
+    fn test_mahalanobis_gate_rejects_distant_detection() {
+        let trackers = vec![KalmanBoxTracker::new(
+            BBox::new(0.0, 0.0, 10.0, 10.0),
+            0,
+            3,
+            0,
+        )];
+        let detections = vec![
+            Detection::new(BBox::new(500.0, 500.0, 510.0, 510.0), 0).with_score(0.9),
+            Detection::new(BBox::new(1.0, 0.0, 11.0, 10.0), 0).with_score(0.9),
+        ];
+
+        // Without IoU threshold every pair of the same class is a valid match.
+        let config = OCSortConfig {
+            iou_threshold: 0.0,
+            ..Default::default()
+        };
+        let (matched_indices, _, _) = associate_detections_to_trackers(
+            &detections,
+            &[0],
+            &trackers,
+            &[0],
+            &config,
+            &HungarianMatcher,
+        );
+        assert_eq!(matched_indices, vec![(0, 0)]);
+
+        let config = OCSortConfig {
+            iou_threshold: 0.0,
+            mahalanobis_gate: Some(CHI_SQUARED_95_4_DOF),
+            ..Default::default()
+        };
+        let (matched_indices, unmatched_detections, unmatched_trackers) =
+            associate_detections_to_trackers(
+                &detections,
+                &[0],
+                &trackers,
+                &[0],
+                &config,
+                &HungarianMatcher,
+            );
+        assert!(matched_indices.is_empty());
+        assert_eq!(unmatched_detections, vec![0]);
+        assert_eq!(unmatched_trackers, vec![0]);
+
+        let (matched_indices, _, _) = associate_detections_to_trackers(
+            &detections,
+            &[0, 1],
+            &trackers,
+            &[0],
+            &config,
+            &HungarianMatcher,
+        );
+        assert_eq!(matched_indices, vec![(1, 0)]);
+    }
+
+    #[test]
+    fn test_iou_threshold_schedule_relaxes_threshold_for_coasting_tracks() {
+        let mut fresh_tracker = KalmanBoxTracker::new(BBox::new(0.0, 0.0, 10.0, 10.0), 0, 3, 0);
+        fresh_tracker.predict();
+        let mut coasting_tracker = KalmanBoxTracker::new(BBox::new(0.0, 0.0, 10.0, 10.0), 0, 3, 1);
+        for _ in 0..3 {
+            coasting_tracker.predict();
+        }
+        let trackers = vec![fresh_tracker, coasting_tracker];
+        // IoU of 0.25 with both trackers.
+        let detections = vec![Detection::new(BBox::new(0.0, 0.0, 10.0, 2.5), 0).with_score(0.9)];
+
+        let config = OCSortConfig::default();
+        for tracker_index in [0, 1] {
+            let (matched_indices, _, _) = associate_detections_to_trackers(
+                &detections,
+                &[0],
+                &trackers,
+                &[tracker_index],
+                &config,
+                &HungarianMatcher,
+            );
+            assert!(matched_indices.is_empty());
+        }
+
+        let config = OCSortConfig {
+            iou_threshold_schedule: Some(IouThresholdSchedule::default()),
+            ..Default::default()
+        };
+        let (matched_indices, _, _) = associate_detections_to_trackers(
+            &detections,
+            &[0],
+            &trackers,
+            &[0],
+            &config,
+            &HungarianMatcher,
+        );
+        assert!(matched_indices.is_empty());
+        let (matched_indices, _, _) = associate_detections_to_trackers(
+            &detections,
+            &[0],
+            &trackers,
+            &[1],
+            &config,
+            &HungarianMatcher,
+        );
+        assert_eq!(matched_indices, vec![(0, 1)]);
+    }
+
+    #[test]
+    fn test_iou_threshold_schedule_never_raises_threshold() {
+        let schedule = IouThresholdSchedule {
+            slope: -0.1,
+            min_threshold: 0.1,
+        };
+        for time_since_update in 0..5 {
+            assert_eq!(schedule.threshold(0.3, time_since_update), 0.3);
+        }
+
+        let schedule = IouThresholdSchedule::default();
+        assert_eq!(schedule.threshold(0.3, 1), 0.3);
+        assert!((schedule.threshold(0.3, 3) - 0.2).abs() < 1e-12);
+        assert_eq!(schedule.threshold(0.3, 100), 0.1);
+        assert_eq!(schedule.threshold(0.05, 100), 0.05);
+    }
+
+    #[test]
+    fn test_ocr_iou_threshold_controls_ocr_stage() {
+        let trackers = vec![KalmanBoxTracker::new(
+            BBox::new(0.0, 0.0, 10.0, 10.0),
+            0,
+            3,
+            0,
+        )];
+        // IoU of 0.5 with the last observation of the tracker.
+        let detections = vec![Detection::new(BBox::new(0.0, 0.0, 10.0, 5.0), 0).with_score(0.9)];
+
+        let config = OCSortConfig::default();
+        let (matched_indices, _, _) = observation_centric_recovery(
+            &detections,
+            &[0],
+            &trackers,
+            &[0],
+            &config,
+            &HungarianMatcher,
+        );
+        assert_eq!(matched_indices, vec![(0, 0)]);
+
+        let config = OCSortConfig {
+            ocr_iou_threshold: Some(0.6),
+            ..Default::default()
+        };
+        let (matched_indices, _, _) = observation_centric_recovery(
+            &detections,
+            &[0],
+            &trackers,
+            &[0],
+            &config,
+            &HungarianMatcher,
+        );
+        assert!(matched_indices.is_empty());
+        // The other stages still use iou_threshold.
+        let (matched_indices, _, _) = byte_associate(
+            &detections,
+            &[0],
+            &trackers,
+            &[0],
+            &config,
+            &HungarianMatcher,
+        );
+        assert_eq!(matched_indices, vec![(0, 0)]);
+    }
 }
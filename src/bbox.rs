@@ -1,9 +1,15 @@
-use std::f64::EPSILON;
+use core::f64::consts::PI;
 
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+// Provides sqrt, atan and powi through libm when the inherent float methods of std are missing.
+#[cfg(not(feature = "std"))]
+use nalgebra::ComplexField;
 use nalgebra::SVector;
 
 /// Struct that represents a bounding box.
-#[derive(Clone, Copy, Default, Debug)]
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BBox {
     /// The top left x coordinate.
     pub x_1: f64,
@@ -30,12 +36,86 @@ impl BBox {
         BBox { x_1, y_1, x_2, y_2 }
     }
 
+    /// Creates a new bounding box from the center (cx, cy), width and height.
+    ///
+    /// Returns the zero bbox if the width or height is negative.
+    pub fn from_cxcywh(cx: f64, cy: f64, w: f64, h: f64) -> Self {
+        Self::new(cx - w / 2.0, cy - h / 2.0, cx + w / 2.0, cy + h / 2.0)
+    }
+
+    /// Returns the (center x, center y, width, height) representation of this bounding box.
+    pub fn to_cxcywh(&self) -> (f64, f64, f64, f64) {
+        let w = self.x_2 - self.x_1;
+        let h = self.y_2 - self.y_1;
+
+        (self.x_1 + w / 2.0, self.y_1 + h / 2.0, w, h)
+    }
+
+    /// Creates a new bounding box from the top left (x, y) coordinates, width and height.
+    ///
+    /// Returns the zero bbox if the width or height is negative.
+    pub fn from_xywh(x: f64, y: f64, w: f64, h: f64) -> Self {
+        Self::new(x, y, x + w, y + h)
+    }
+
+    /// Returns the (top left x, top left y, width, height) representation of this bounding box.
+    pub fn to_xywh(&self) -> (f64, f64, f64, f64) {
+        (self.x_1, self.y_1, self.x_2 - self.x_1, self.y_2 - self.y_1)
+    }
+
+    /// Creates a new bounding box from f32 top left (x_1, y_1) and bottom right (x_2, y_2)
+    /// coordinates. The conversion to f64 is lossless.
+    pub fn from_f32(x_1: f32, y_1: f32, x_2: f32, y_2: f32) -> Self {
+        Self::new(x_1.into(), y_1.into(), x_2.into(), y_2.into())
+    }
+
+    /// Returns the (x_1, y_1, x_2, y_2) coordinates of this bounding box rounded to f32.
+    pub fn as_f32(&self) -> (f32, f32, f32, f32) {
+        (
+            self.x_1 as f32,
+            self.y_1 as f32,
+            self.x_2 as f32,
+            self.y_2 as f32,
+        )
+    }
+
+    /// Creates a new bounding box in pixel coordinates from coordinates normalized
+    /// to the range [0, 1] relative to an image of size img_w x img_h.
+    ///
+    /// Returns the zero bbox if the image width or height is not positive.
+    pub fn from_normalized(x_1: f64, y_1: f64, x_2: f64, y_2: f64, img_w: f64, img_h: f64) -> Self {
+        if img_w <= 0.0 || img_h <= 0.0 {
+            return BBox::default();
+        }
+        Self::new(x_1 * img_w, y_1 * img_h, x_2 * img_w, y_2 * img_h)
+    }
+
+    /// Returns this bounding box with coordinates normalized relative to an image
+    /// of size img_w x img_h.
+    ///
+    /// Returns the zero bbox if the image width or height is not positive.
+    pub fn to_normalized(&self, img_w: f64, img_h: f64) -> Self {
+        if img_w <= 0.0 || img_h <= 0.0 {
+            return BBox::default();
+        }
+        Self::new(
+            self.x_1 / img_w,
+            self.y_1 / img_h,
+            self.x_2 / img_w,
+            self.y_2 / img_h,
+        )
+    }
+
     /// Creates a bounding box from a Kalman Filter state vector.
     pub fn from_state_vector(state_vector: SVector<f64, 7>) -> Self {
         if state_vector[2] < 0.0 || state_vector[3] < 0.0 {
             return BBox::new(0.0, 0.0, 0.0, 0.0);
         }
         let w = (state_vector[2] * state_vector[3]).sqrt();
+        if w == 0.0 {
+            // A zero area or ratio would make the height 0 / 0 or x / 0.
+            return BBox::new(0.0, 0.0, 0.0, 0.0);
+        }
         let h = state_vector[2] / w;
 
         Self::new(
@@ -48,19 +128,101 @@ impl BBox {
 
     /// Creates a Kalman Filter observation vector out of this bounding box.
     pub fn to_observation_vector(&self) -> SVector<f64, 4> {
-        let w = (self.x_2 - self.x_1).max(0.0);
-        let h = (self.y_2 - self.y_1).max(0.0);
+        let w = self.width();
+        let h = self.height();
 
-        let cx = self.x_1 + w / 2.0;
-        let cy = self.y_1 + h / 2.0;
+        let (cx, cy) = self.center();
         let area = w * h;
-        let r = w / (h + EPSILON);
+        let r = w / (h + f64::EPSILON);
 
         SVector::<f64, 4>::new(cx, cy, area, r)
     }
 
+    /// Returns the (x, y) coordinates of the center of this bounding box.
+    pub fn center(&self) -> (f64, f64) {
+        (
+            self.x_1 + self.width() / 2.0,
+            self.y_1 + self.height() / 2.0,
+        )
+    }
+
+    /// Returns the width of this bounding box. Returns 0 if negative.
+    pub fn width(&self) -> f64 {
+        (self.x_2 - self.x_1).max(0.0)
+    }
+
+    /// Returns the height of this bounding box. Returns 0 if negative.
+    pub fn height(&self) -> f64 {
+        (self.y_2 - self.y_1).max(0.0)
+    }
+
+    /// Returns this bounding box clipped to the frame [0, width] x [0, height].
+    ///
+    /// A box fully outside the frame collapses onto the nearest frame edge.
+    pub fn clamp(&self, width: f64, height: f64) -> Self {
+        let width = width.max(0.0);
+        let height = height.max(0.0);
+
+        Self::new(
+            self.x_1.clamp(0.0, width),
+            self.y_1.clamp(0.0, height),
+            self.x_2.clamp(0.0, width),
+            self.y_2.clamp(0.0, height),
+        )
+    }
+
+    /// Returns true if this bounding box has a zero width or height, e.g. x_1 == x_2, or a
+    /// non-finite coordinate. Such boxes have no meaningful area or aspect ratio.
+    pub fn is_degenerate(&self) -> bool {
+        let finite = [self.x_1, self.y_1, self.x_2, self.y_2]
+            .iter()
+            .all(|coordinate| coordinate.is_finite());
+        !(finite && self.x_2 > self.x_1 && self.y_2 > self.y_1)
+    }
+
+    /// Returns this bounding box grown around its center to be at least min_size wide and
+    /// high. Larger boxes are returned unchanged.
+    pub fn with_min_size(&self, min_size: f64) -> Self {
+        let (cx, cy, w, h) = self.to_cxcywh();
+
+        Self::from_cxcywh(cx, cy, w.max(min_size), h.max(min_size))
+    }
+
+    /// Returns this bounding box with the x coordinates multiplied by sx and the
+    /// y coordinates by sy.
+    pub fn scale(&self, sx: f64, sy: f64) -> Self {
+        self.affine(sx, sy, 0.0, 0.0)
+    }
+
+    /// Returns this bounding box moved by dx along the x axis and dy along the y axis.
+    pub fn translate(&self, dx: f64, dy: f64) -> Self {
+        self.affine(1.0, 1.0, dx, dy)
+    }
+
+    /// Returns this bounding box with the coordinates mapped with x * sx + dx and
+    /// y * sy + dy, e.g. to undo letterbox preprocessing.
+    ///
+    /// The corners are reordered so that negative scales flip the box instead of
+    /// producing an invalid one.
+    pub fn affine(&self, sx: f64, sy: f64, dx: f64, dy: f64) -> Self {
+        let (x_1, x_2) = (self.x_1 * sx + dx, self.x_2 * sx + dx);
+        let (y_1, y_2) = (self.y_1 * sy + dy, self.y_2 * sy + dy);
+
+        Self::new(x_1.min(x_2), y_1.min(y_2), x_1.max(x_2), y_1.max(y_2))
+    }
+
     /// Calculates the intersection over union value between this bounding box and a given bbox.
+    ///
+    /// Returns 0 without computing the areas for disjoint or edge touching boxes.
     pub fn iou(&self, other: &Self) -> f64 {
+        if self.x_2 <= other.x_1
+            || other.x_2 <= self.x_1
+            || self.y_2 <= other.y_1
+            || other.y_2 <= self.y_1
+        {
+            return 0.0;
+        }
+
         let iwidth = (self.x_2.min(other.x_2) - self.x_1.max(other.x_1)).max(0.0);
         let iheight = (self.y_2.min(other.y_2) - self.y_1.max(other.y_1)).max(0.0);
         let iarea = iwidth * iheight;
@@ -74,6 +236,93 @@ impl BBox {
         iarea / union
     }
 
+    /// Calculates the intersection over union value with the inclusive pixel grid convention,
+    /// where the corners are pixel indices and the width of a box is x_2 - x_1 + 1.
+    ///
+    /// The default iou treats the boxes as continuous regions, which matches TrackEval,
+    /// py-motmetrics and the KITTI devkit. Use this variant to compare against tools that
+    /// follow the Pascal VOC devkit convention of adding 1 to the width and height.
+    pub fn iou_pixel(&self, other: &Self) -> f64 {
+        let pixel_area = |bbox: &Self| {
+            (bbox.x_2 - bbox.x_1 + 1.0).max(0.0) * (bbox.y_2 - bbox.y_1 + 1.0).max(0.0)
+        };
+
+        let iwidth = (self.x_2.min(other.x_2) - self.x_1.max(other.x_1) + 1.0).max(0.0);
+        let iheight = (self.y_2.min(other.y_2) - self.y_1.max(other.y_1) + 1.0).max(0.0);
+        let iarea = iwidth * iheight;
+
+        let union = pixel_area(self) + pixel_area(other) - iarea;
+
+        if union <= 0.0 {
+            return 0.0;
+        }
+
+        iarea / union
+    }
+
+    /// Calculates the generalized intersection over union value between this bounding box
+    /// and a given bbox.
+    ///
+    /// Subtracts the portion of the smallest enclosing box not covered by the union
+    /// from the iou. The value is in the range [-1, 1].
+    pub fn giou(&self, other: &Self) -> f64 {
+        let iou = self.iou(other);
+        let enclosing_area = self.union(other).area();
+
+        if enclosing_area <= 0.0 {
+            return iou;
+        }
+
+        let iwidth = (self.x_2.min(other.x_2) - self.x_1.max(other.x_1)).max(0.0);
+        let iheight = (self.y_2.min(other.y_2) - self.y_1.max(other.y_1)).max(0.0);
+        let union = self.area() + other.area() - iwidth * iheight;
+
+        iou - (enclosing_area - union) / enclosing_area
+    }
+
+    /// Calculates the distance intersection over union value between this bounding box
+    /// and a given bbox.
+    ///
+    /// Penalizes the iou with the squared distance between the box centers normalized by
+    /// the squared diagonal of the smallest enclosing box. The value is in the range [-1, 1].
+    pub fn diou(&self, other: &Self) -> f64 {
+        let iou = self.iou(other);
+        let enclosing = self.union(other);
+        let diagonal =
+            (enclosing.x_2 - enclosing.x_1).powi(2) + (enclosing.y_2 - enclosing.y_1).powi(2);
+
+        if diagonal <= 0.0 {
+            return iou;
+        }
+
+        let center_distance = ((self.x_1 + self.x_2) - (other.x_1 + other.x_2)).powi(2) / 4.0
+            + ((self.y_1 + self.y_2) - (other.y_1 + other.y_2)).powi(2) / 4.0;
+
+        iou - center_distance / diagonal
+    }
+
+    /// Calculates the complete intersection over union value between this bounding box
+    /// and a given bbox.
+    ///
+    /// Extends diou with a penalty for the mismatch between the aspect ratios of the boxes.
+    /// The value is clamped to the range [-1, 1], which the penalty can otherwise exceed
+    /// for distant boxes of very different aspect ratios.
+    pub fn ciou(&self, other: &Self) -> f64 {
+        let iou = self.iou(other);
+        let diou = self.diou(other);
+
+        let arctan_1 = ((self.x_2 - self.x_1) / (self.y_2 - self.y_1 + f64::EPSILON)).atan();
+        let arctan_2 = ((other.x_2 - other.x_1) / (other.y_2 - other.y_1 + f64::EPSILON)).atan();
+        let v = 4.0 / (PI * PI) * (arctan_1 - arctan_2).powi(2);
+
+        if v == 0.0 {
+            return diou;
+        }
+        let alpha = v / (1.0 - iou + v);
+
+        (diou - alpha * v).max(-1.0)
+    }
+
     /// Calculates the area of this bounding box. Returns 0 if negative.
     pub fn area(&self) -> f64 {
         ((self.x_2 - self.x_1) * (self.y_2 - self.y_1)).max(0.0)
@@ -92,12 +341,104 @@ impl BBox {
 
         SVector::<f64, 2>::zeros()
     }
+
+    /// Returns true if the point (x, y) is inside this bounding box or on its edge.
+    pub fn contains_point(&self, x: f64, y: f64) -> bool {
+        self.x_1 <= x && x <= self.x_2 && self.y_1 <= y && y <= self.y_2
+    }
+
+    /// Returns true if the other bounding box is fully inside this bounding box.
+    /// Shared edges count as inside.
+    pub fn contains(&self, other: &Self) -> bool {
+        self.x_1 <= other.x_1
+            && self.y_1 <= other.y_1
+            && other.x_2 <= self.x_2
+            && other.y_2 <= self.y_2
+    }
+
+    /// Returns the overlapping region of this and the other bounding box.
+    ///
+    /// Returns None if the boxes are disjoint or only touch at an edge, i.e. the
+    /// intersection would be the zero bbox.
+    pub fn intersection(&self, other: &Self) -> Option<BBox> {
+        let x_1 = self.x_1.max(other.x_1);
+        let y_1 = self.y_1.max(other.y_1);
+        let x_2 = self.x_2.min(other.x_2);
+        let y_2 = self.y_2.min(other.y_2);
+
+        (x_1 < x_2 && y_1 < y_2).then(|| BBox::new(x_1, y_1, x_2, y_2))
+    }
+
+    /// Returns the smallest bounding box that contains both this and the other bounding box.
+    pub fn union(&self, other: &Self) -> BBox {
+        BBox::new(
+            self.x_1.min(other.x_1),
+            self.y_1.min(other.y_1),
+            self.x_2.max(other.x_2),
+            self.y_2.max(other.y_2),
+        )
+    }
+}
+
+impl From<[f64; 4]> for BBox {
+    /// Creates a bounding box from [x_1, y_1, x_2, y_2] with BBox::new, so invalid boxes
+    /// become the zero bbox.
+    fn from([x_1, y_1, x_2, y_2]: [f64; 4]) -> Self {
+        Self::new(x_1, y_1, x_2, y_2)
+    }
+}
+
+impl From<(f64, f64, f64, f64)> for BBox {
+    /// Creates a bounding box from (x_1, y_1, x_2, y_2) with BBox::new, so invalid boxes
+    /// become the zero bbox.
+    fn from((x_1, y_1, x_2, y_2): (f64, f64, f64, f64)) -> Self {
+        Self::new(x_1, y_1, x_2, y_2)
+    }
+}
+
+impl From<BBox> for [f64; 4] {
+    /// Returns the [x_1, y_1, x_2, y_2] coordinates of the bounding box.
+    fn from(bbox: BBox) -> Self {
+        [bbox.x_1, bbox.y_1, bbox.x_2, bbox.y_2]
+    }
+}
+
+/// Calculates the intersection over union of every pair of bounding boxes. Row i of the
+/// result holds the IoUs of `bboxes_1[i]` with each of bboxes_2.
+pub fn iou_batch(bboxes_1: &[BBox], bboxes_2: &[BBox]) -> Vec<Vec<f64>> {
+    bboxes_1
+        .iter()
+        .map(|bbox_1| bboxes_2.iter().map(|bbox_2| bbox_1.iou(bbox_2)).collect())
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_is_degenerate() {
+        assert!(!BBox::new(0.0, 0.0, 1.0, 1.0).is_degenerate());
+        assert!(BBox::new(1.0, 0.0, 1.0, 5.0).is_degenerate());
+        assert!(BBox::new(0.0, 2.0, 5.0, 2.0).is_degenerate());
+        assert!(BBox::new(0.0, 0.0, f64::INFINITY, 1.0).is_degenerate());
+        assert!(
+            BBox {
+                x_1: f64::NAN,
+                ..BBox::new(0.0, 0.0, 1.0, 1.0)
+            }
+            .is_degenerate()
+        );
+    }
+
+    #[test]
+    fn test_with_min_size_grows_around_center() {
+        let bbox = BBox::new(4.0, 2.0, 4.0, 12.0);
+
+        assert_eq!(bbox.with_min_size(2.0), BBox::new(3.0, 2.0, 5.0, 12.0));
+        assert_eq!(bbox.with_min_size(2.0).center(), bbox.center());
+    }
+
     #[test]
     fn test_invalid_new_bbox_returns_zero_bbox() {
         let bbox = BBox::new(3.0, 4.0, 2.0, 5.0);
@@ -108,6 +449,145 @@ mod tests {
         assert_eq!(bbox.y_2, 0.0);
     }
 
+    #[test]
+    fn test_f32_conversions_preserve_iou() {
+        let bbox_1 = BBox::new(0.0, 0.0, 10.5, 10.25);
+        let bbox_2 = BBox::new(2.5, 1.0, 12.0, 9.75);
+        let (x_1, y_1, x_2, y_2) = bbox_1.as_f32();
+        let f32_bbox_1 = BBox::from_f32(x_1, y_1, x_2, y_2);
+        let (x_1, y_1, x_2, y_2) = bbox_2.as_f32();
+        let f32_bbox_2 = BBox::from_f32(x_1, y_1, x_2, y_2);
+
+        assert_eq!(f32_bbox_1, bbox_1);
+        assert!((f32_bbox_1.iou(&f32_bbox_2) - bbox_1.iou(&bbox_2)).abs() < 1e-6);
+
+        let f32_bbox = BBox::from_f32(0.1, 0.2, 10.3, 10.4);
+        let iou = f32_bbox.iou(&BBox::new(0.1, 0.2, 10.3, 10.4));
+        assert!((iou - 1.0).abs() < 1e-6);
+        assert_eq!(f32_bbox.as_f32(), (0.1, 0.2, 10.3, 10.4));
+    }
+
+    #[test]
+    fn test_cxcywh_round_trip() {
+        let bbox = BBox::new(10.0, 20.0, 40.0, 60.0);
+
+        assert_eq!(bbox.to_cxcywh(), (25.0, 40.0, 30.0, 40.0));
+
+        let (cx, cy, w, h) = bbox.to_cxcywh();
+        let round_trip = BBox::from_cxcywh(cx, cy, w, h);
+        assert_eq!(round_trip.x_1, 10.0);
+        assert_eq!(round_trip.y_1, 20.0);
+        assert_eq!(round_trip.x_2, 40.0);
+        assert_eq!(round_trip.y_2, 60.0);
+    }
+
+    #[test]
+    fn test_from_cxcywh_returns_zero_bbox_for_negative_size() {
+        let bbox = BBox::from_cxcywh(5.0, 5.0, -2.0, 3.0);
+
+        assert_eq!(bbox.x_1, 0.0);
+        assert_eq!(bbox.x_2, 0.0);
+        assert_eq!(bbox.y_1, 0.0);
+        assert_eq!(bbox.y_2, 0.0);
+    }
+
+    #[test]
+    fn test_from_xywh_returns_correct_bbox() {
+        let bbox = BBox::from_xywh(10.0, 20.0, 5.0, 7.0);
+
+        assert_eq!(bbox.x_1, 10.0);
+        assert_eq!(bbox.y_1, 20.0);
+        assert_eq!(bbox.x_2, 15.0);
+        assert_eq!(bbox.y_2, 27.0);
+        assert_eq!(bbox.to_xywh(), (10.0, 20.0, 5.0, 7.0));
+    }
+
+    #[test]
+    fn test_from_xywh_returns_zero_bbox_for_negative_size() {
+        let bbox = BBox::from_xywh(10.0, 20.0, 5.0, -7.0);
+
+        assert_eq!(bbox.to_xywh(), (0.0, 0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_from_normalized_returns_pixel_bbox() {
+        let bbox = BBox::from_normalized(0.25, 0.25, 0.75, 0.75, 1920.0, 1080.0);
+
+        assert_eq!(bbox.x_1, 480.0);
+        assert_eq!(bbox.y_1, 270.0);
+        assert_eq!(bbox.x_2, 1440.0);
+        assert_eq!(bbox.y_2, 810.0);
+    }
+
+    #[test]
+    fn test_to_normalized_returns_normalized_bbox() {
+        let bbox = BBox::new(0.0, 0.0, 960.0, 1080.0).to_normalized(1920.0, 1080.0);
+
+        assert_eq!(bbox.x_1, 0.0);
+        assert_eq!(bbox.y_1, 0.0);
+        assert_eq!(bbox.x_2, 0.5);
+        assert_eq!(bbox.y_2, 1.0);
+    }
+
+    #[test]
+    fn test_normalized_conversions_return_zero_bbox_for_empty_image() {
+        let bbox = BBox::from_normalized(0.25, 0.25, 0.75, 0.75, 0.0, 1080.0);
+        assert_eq!(bbox.to_xywh(), (0.0, 0.0, 0.0, 0.0));
+
+        let bbox = BBox::new(0.0, 0.0, 960.0, 1080.0).to_normalized(1920.0, 0.0);
+        assert_eq!(bbox.to_xywh(), (0.0, 0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_center_and_dimensions_of_known_box() {
+        let bbox = BBox::new(2.0, 4.0, 8.0, 14.0);
+
+        assert_eq!(bbox.center(), (5.0, 9.0));
+        assert_eq!(bbox.width(), 6.0);
+        assert_eq!(bbox.height(), 10.0);
+    }
+
+    #[test]
+    fn test_letterbox_round_trip() {
+        // A 1280x720 frame letterboxed into a 640x640 input: scaled by 0.5 and padded
+        // by 140 pixels on the top and bottom.
+        let bbox = BBox::new(100.0, 200.0, 300.0, 500.0);
+
+        let letterboxed = bbox.affine(0.5, 0.5, 0.0, 140.0);
+        assert_eq!(letterboxed, BBox::new(50.0, 240.0, 150.0, 390.0));
+
+        let unletterboxed = letterboxed.translate(0.0, -140.0).scale(2.0, 2.0);
+        assert_eq!(unletterboxed, bbox);
+    }
+
+    #[test]
+    fn test_negative_scale_flips_box() {
+        let bbox = BBox::new(1.0, 2.0, 3.0, 4.0);
+
+        assert_eq!(bbox.scale(-1.0, 1.0), BBox::new(-3.0, 2.0, -1.0, 4.0));
+    }
+
+    #[test]
+    fn test_clamp_clips_partially_outside_bbox() {
+        let bbox = BBox::new(-10.0, 50.0, 100.0, 250.0).clamp(200.0, 200.0);
+
+        assert_eq!(bbox.x_1, 0.0);
+        assert_eq!(bbox.y_1, 50.0);
+        assert_eq!(bbox.x_2, 100.0);
+        assert_eq!(bbox.y_2, 200.0);
+    }
+
+    #[test]
+    fn test_clamp_collapses_fully_outside_bbox() {
+        let bbox = BBox::new(250.0, 10.0, 300.0, 20.0).clamp(200.0, 200.0);
+
+        assert_eq!(bbox.x_1, 200.0);
+        assert_eq!(bbox.y_1, 10.0);
+        assert_eq!(bbox.x_2, 200.0);
+        assert_eq!(bbox.y_2, 20.0);
+        assert_eq!(bbox.area(), 0.0);
+    }
+
     #[test]
     fn test_from_state_vector_returns_zero_bbox_for_invalid_state() {
         let state_vector = SVector::<f64, 7>::from_vec(vec![1.0, 1.0, 4.0, -1.0, 0.0, 0.0, 0.0]);
@@ -119,6 +599,31 @@ mod tests {
         assert_eq!(bbox.y_2, 0.0);
     }
 
+    #[test]
+    fn test_from_state_vector_returns_zero_bbox_for_zero_area() {
+        let state_vector = SVector::<f64, 7>::from_vec(vec![1.0, 1.0, 0.0, 2.0, 0.0, 0.0, 0.0]);
+        let bbox = BBox::from_state_vector(state_vector);
+
+        assert_eq!(bbox, BBox::new(0.0, 0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_bbox_converts_from_and_to_arrays_and_tuples() {
+        let bbox = BBox::new(1.0, 2.0, 3.0, 4.0);
+
+        assert_eq!(BBox::from([1.0, 2.0, 3.0, 4.0]), bbox);
+        assert_eq!(BBox::from((1.0, 2.0, 3.0, 4.0)), bbox);
+        assert_eq!(<[f64; 4]>::from(bbox), [1.0, 2.0, 3.0, 4.0]);
+        let coordinates: [f64; 4] = BBox::from([1.0, 2.0, 3.0, 4.0]).into();
+        assert_eq!(coordinates, [1.0, 2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn test_bbox_conversions_normalize_invalid_boxes() {
+        assert_eq!(BBox::from([3.0, 2.0, 1.0, 4.0]), BBox::default());
+        assert_eq!(BBox::from((1.0, 4.0, 3.0, 2.0)), BBox::default());
+    }
+
     #[test]
     fn test_iou_returns_correct_value_1() {
         let bbox_1 = BBox::new(1.0, 1.0, 2.0, 2.0);
@@ -142,4 +647,150 @@ mod tests {
 
         assert_eq!(bbox_1.iou(&bbox_2), 1.0 / 9.0)
     }
+
+    #[test]
+    fn test_iou_of_edge_touching_boxes_is_zero() {
+        let bbox = BBox::new(0.0, 0.0, 10.0, 10.0);
+
+        assert_eq!(bbox.iou(&BBox::new(10.0, 0.0, 20.0, 10.0)), 0.0);
+        assert_eq!(bbox.iou(&BBox::new(0.0, 10.0, 10.0, 20.0)), 0.0);
+        assert_eq!(bbox.iou(&BBox::new(10.0, 10.0, 20.0, 20.0)), 0.0);
+        assert_eq!(BBox::new(-10.0, 0.0, 0.0, 10.0).iou(&bbox), 0.0);
+    }
+
+    #[test]
+    fn test_iou_pixel_adds_one_to_width_and_height() {
+        let bbox_1 = BBox::new(0.0, 0.0, 9.0, 9.0);
+        let bbox_2 = BBox::new(5.0, 0.0, 14.0, 9.0);
+
+        assert_eq!(bbox_1.iou(&bbox_2), 36.0 / 126.0);
+        assert_eq!(bbox_1.iou_pixel(&bbox_2), 50.0 / 150.0);
+    }
+
+    #[test]
+    fn test_iou_pixel_of_edge_touching_boxes_shares_a_pixel_column() {
+        let bbox_1 = BBox::new(0.0, 0.0, 9.0, 9.0);
+        let bbox_2 = BBox::new(9.0, 0.0, 18.0, 9.0);
+
+        assert_eq!(bbox_1.iou(&bbox_2), 0.0);
+        assert_eq!(bbox_1.iou_pixel(&bbox_2), 10.0 / 190.0);
+        assert_eq!(bbox_1.iou_pixel(&BBox::new(10.0, 0.0, 19.0, 9.0)), 0.0);
+    }
+
+    #[test]
+    fn test_geometry_of_nested_boxes() {
+        let outer = BBox::new(0.0, 0.0, 10.0, 10.0);
+        let inner = BBox::new(2.0, 3.0, 5.0, 10.0);
+
+        assert!(outer.contains(&inner));
+        assert!(!inner.contains(&outer));
+        assert!(outer.contains_point(10.0, 0.0));
+        assert!(!inner.contains_point(1.0, 5.0));
+        assert_eq!(outer.intersection(&inner), Some(inner));
+        assert_eq!(outer.union(&inner), outer);
+    }
+
+    #[test]
+    fn test_geometry_of_overlapping_boxes() {
+        let bbox_1 = BBox::new(0.0, 0.0, 10.0, 10.0);
+        let bbox_2 = BBox::new(5.0, -5.0, 15.0, 5.0);
+
+        assert!(!bbox_1.contains(&bbox_2));
+        assert!(!bbox_2.contains(&bbox_1));
+        assert_eq!(
+            bbox_1.intersection(&bbox_2),
+            Some(BBox::new(5.0, 0.0, 10.0, 5.0))
+        );
+        assert_eq!(bbox_1.union(&bbox_2), BBox::new(0.0, -5.0, 15.0, 10.0));
+    }
+
+    #[test]
+    fn test_geometry_of_disjoint_boxes() {
+        let bbox_1 = BBox::new(0.0, 0.0, 10.0, 10.0);
+        let bbox_2 = BBox::new(20.0, 0.0, 30.0, 10.0);
+
+        assert!(!bbox_1.contains(&bbox_2));
+        assert!(!bbox_1.contains_point(15.0, 5.0));
+        assert_eq!(bbox_1.intersection(&bbox_2), None);
+        assert_eq!(bbox_1.intersection(&BBox::new(10.0, 0.0, 20.0, 10.0)), None);
+        assert_eq!(bbox_1.union(&bbox_2), BBox::new(0.0, 0.0, 30.0, 10.0));
+    }
+
+    #[test]
+    fn test_iou_batch_returns_pairwise_ious() {
+        let bboxes_1 = [BBox::new(0.0, 0.0, 2.0, 2.0), BBox::new(1.0, 1.0, 3.0, 3.0)];
+        let bboxes_2 = [BBox::new(0.0, 0.0, 2.0, 2.0), BBox::new(2.0, 0.0, 4.0, 2.0)];
+
+        let ious = iou_batch(&bboxes_1, &bboxes_2);
+
+        // The second pair of the first row only touches at an edge. The second row
+        // overlaps both boxes by one unit of area out of a union of seven.
+        assert_eq!(ious, vec![vec![1.0, 0.0], vec![1.0 / 7.0, 1.0 / 7.0]]);
+    }
+
+    #[test]
+    fn test_giou_is_negative_for_non_overlapping_boxes() {
+        let bbox_1 = BBox::new(0.0, 0.0, 1.0, 1.0);
+        let bbox_2 = BBox::new(2.0, 0.0, 3.0, 1.0);
+
+        assert_eq!(bbox_1.giou(&bbox_2), -1.0 / 3.0)
+    }
+
+    #[test]
+    fn test_giou_decreases_with_distance_between_non_overlapping_boxes() {
+        let bbox_1 = BBox::new(0.0, 0.0, 1.0, 1.0);
+        let near = BBox::new(1.5, 0.0, 2.5, 1.0);
+        let far = BBox::new(5.0, 0.0, 6.0, 1.0);
+
+        assert_eq!(bbox_1.iou(&near), bbox_1.iou(&far));
+        assert!(bbox_1.giou(&near) > bbox_1.giou(&far));
+        assert!(bbox_1.giou(&far) < 0.0);
+    }
+
+    #[test]
+    fn test_giou_equals_iou_for_nested_boxes() {
+        let bbox_1 = BBox::new(0.0, 0.0, 3.0, 3.0);
+        let bbox_2 = BBox::new(1.0, 1.0, 2.0, 2.0);
+
+        assert_eq!(bbox_1.giou(&bbox_2), bbox_1.iou(&bbox_2))
+    }
+
+    #[test]
+    fn test_diou_prefers_closer_centered_box_with_identical_iou() {
+        let bbox = BBox::new(0.0, 0.0, 1.0, 1.0);
+        let near = BBox::new(1.5, 0.0, 2.5, 1.0);
+        let far = BBox::new(3.0, 0.0, 4.0, 1.0);
+
+        assert_eq!(bbox.iou(&near), bbox.iou(&far));
+        assert!(bbox.diou(&near) > bbox.diou(&far));
+    }
+
+    #[test]
+    fn test_diou_equals_iou_for_concentric_boxes() {
+        let bbox_1 = BBox::new(0.0, 0.0, 3.0, 3.0);
+        let bbox_2 = BBox::new(1.0, 1.0, 2.0, 2.0);
+
+        assert_eq!(bbox_1.diou(&bbox_2), bbox_1.iou(&bbox_2))
+    }
+
+    #[test]
+    fn test_ciou_penalizes_aspect_ratio_mismatch() {
+        let bbox = BBox::new(0.0, 0.0, 2.0, 2.0);
+        let same_ratio = BBox::new(0.5, 0.5, 1.5, 1.5);
+        let other_ratio = BBox::new(0.0, 0.5, 2.0, 1.5);
+
+        assert_eq!(bbox.ciou(&same_ratio), bbox.diou(&same_ratio));
+        assert!(bbox.ciou(&other_ratio) < bbox.diou(&other_ratio));
+    }
+
+    #[test]
+    fn test_ciou_of_distant_elongated_boxes_stays_in_range() {
+        let tall = BBox::new(0.0, 0.0, 0.001, 1.0);
+        let wide = BBox::new(99.0, 99.999, 100.0, 100.0);
+
+        let ciou = tall.ciou(&wide);
+
+        assert!(tall.diou(&wide) < -0.99);
+        assert!((-1.0..=tall.diou(&wide)).contains(&ciou));
+    }
 }
@@ -0,0 +1,165 @@
+use nalgebra::SVector;
+
+/// Struct that represents an axis aligned 3D bounding box, e.g. from a LiDAR or stereo
+/// detector.
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BBox3D {
+    /// The center x coordinate.
+    pub cx: f64,
+    /// The center y coordinate.
+    pub cy: f64,
+    /// The center z coordinate.
+    pub cz: f64,
+    /// The extent of the box along the x axis.
+    pub width: f64,
+    /// The extent of the box along the y axis.
+    pub height: f64,
+    /// The extent of the box along the z axis.
+    pub length: f64,
+}
+
+impl BBox3D {
+    /// Creates a new 3D bounding box from the center (cx, cy, cz), width, height and length.
+    ///
+    /// Returns a zero size box at the center if the width, height or length is negative.
+    pub fn new(cx: f64, cy: f64, cz: f64, width: f64, height: f64, length: f64) -> Self {
+        if width < 0.0 || height < 0.0 || length < 0.0 {
+            return BBox3D {
+                cx,
+                cy,
+                cz,
+                width: 0.0,
+                height: 0.0,
+                length: 0.0,
+            };
+        }
+        BBox3D {
+            cx,
+            cy,
+            cz,
+            width,
+            height,
+            length,
+        }
+    }
+
+    /// Creates a 3D bounding box out of the Kalman Filter state vector
+    /// [cx, cy, cz, width, height, length, vx, vy, vz].
+    pub fn from_state_vector(state_vector: SVector<f64, 9>) -> Self {
+        Self::new(
+            state_vector[0],
+            state_vector[1],
+            state_vector[2],
+            state_vector[3],
+            state_vector[4],
+            state_vector[5],
+        )
+    }
+
+    /// Creates a Kalman Filter observation vector [cx, cy, cz, width, height, length]
+    /// out of this bounding box.
+    pub fn to_observation_vector(&self) -> SVector<f64, 6> {
+        SVector::<f64, 6>::new(
+            self.cx,
+            self.cy,
+            self.cz,
+            self.width,
+            self.height,
+            self.length,
+        )
+    }
+
+    /// Returns the (min, max) corners of the box.
+    pub fn min_max(&self) -> ([f64; 3], [f64; 3]) {
+        let half_extents = [self.width / 2.0, self.height / 2.0, self.length / 2.0];
+        let center = [self.cx, self.cy, self.cz];
+
+        (
+            [0, 1, 2].map(|axis| center[axis] - half_extents[axis]),
+            [0, 1, 2].map(|axis| center[axis] + half_extents[axis]),
+        )
+    }
+
+    /// Returns the volume of the box.
+    pub fn volume(&self) -> f64 {
+        self.width * self.height * self.length
+    }
+
+    /// Calculates the intersection over union of the volumes of this bounding box and a
+    /// given one.
+    pub fn iou(&self, other: &Self) -> f64 {
+        let (min_1, max_1) = self.min_max();
+        let (min_2, max_2) = other.min_max();
+        let intersection: f64 = (0..3)
+            .map(|axis| (max_1[axis].min(max_2[axis]) - min_1[axis].max(min_2[axis])).max(0.0))
+            .product();
+        let union = self.volume() + other.volume() - intersection;
+
+        if union <= 0.0 {
+            return 0.0;
+        }
+
+        intersection / union
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iou_of_identical_boxes_is_one() {
+        let bbox = BBox3D::new(1.0, 2.0, 3.0, 4.0, 2.0, 6.0);
+
+        assert_eq!(bbox.iou(&bbox), 1.0);
+    }
+
+    #[test]
+    fn test_iou_of_overlapping_boxes() {
+        // Unit cubes shifted by half along x overlap in half a cube.
+        let bbox_1 = BBox3D::new(0.0, 0.0, 0.0, 1.0, 1.0, 1.0);
+        let bbox_2 = BBox3D::new(0.5, 0.0, 0.0, 1.0, 1.0, 1.0);
+        assert!((bbox_1.iou(&bbox_2) - 1.0 / 3.0).abs() < 1e-9);
+
+        // Shifted by half along every axis, they overlap in an eighth of a cube.
+        let bbox_3 = BBox3D::new(0.5, 0.5, 0.5, 1.0, 1.0, 1.0);
+        assert!((bbox_1.iou(&bbox_3) - 0.125 / 1.875).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_iou_of_nested_boxes() {
+        let outer = BBox3D::new(0.0, 0.0, 0.0, 2.0, 2.0, 2.0);
+        let inner = BBox3D::new(0.5, 0.0, -0.5, 1.0, 1.0, 1.0);
+
+        assert!((outer.iou(&inner) - 1.0 / 8.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_iou_of_boxes_separated_along_one_axis_is_zero() {
+        let bbox_1 = BBox3D::new(0.0, 0.0, 0.0, 1.0, 1.0, 1.0);
+        // Overlapping in x and y but not in z.
+        let bbox_2 = BBox3D::new(0.0, 0.0, 2.0, 1.0, 1.0, 1.0);
+
+        assert_eq!(bbox_1.iou(&bbox_2), 0.0);
+    }
+
+    #[test]
+    fn test_negative_size_is_zero_size() {
+        let bbox = BBox3D::new(1.0, 2.0, 3.0, -1.0, 1.0, 1.0);
+
+        assert_eq!(bbox, BBox3D::new(1.0, 2.0, 3.0, 0.0, 0.0, 0.0));
+        assert_eq!(bbox.iou(&bbox), 0.0);
+    }
+
+    #[test]
+    fn test_state_vector_round_trip() {
+        let bbox = BBox3D::new(3.0, 4.0, 5.0, 6.0, 2.0, 1.5);
+        let mut state_vector = SVector::<f64, 9>::zeros();
+        state_vector
+            .fixed_rows_mut::<6>(0)
+            .copy_from(&bbox.to_observation_vector());
+
+        assert_eq!(BBox3D::from_state_vector(state_vector), bbox);
+    }
+}
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+/// Maps numeric class ids to class names, e.g. the labels of a detector.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClassRegistry {
+    names: HashMap<u32, String>,
+}
+
+impl ClassRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the name of the class id, replacing any previous name.
+    pub fn insert(&mut self, class: u32, name: impl Into<String>) {
+        self.names.insert(class, name.into());
+    }
+
+    /// Returns the name of the class id, or None if it has no name.
+    pub fn name(&self, class: u32) -> Option<&str> {
+        self.names.get(&class).map(String::as_str)
+    }
+
+    /// Returns the number of named classes.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Returns true if no class has a name.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+impl<S: Into<String>> FromIterator<(u32, S)> for ClassRegistry {
+    fn from_iter<T: IntoIterator<Item = (u32, S)>>(iter: T) -> Self {
+        Self {
+            names: iter
+                .into_iter()
+                .map(|(class, name)| (class, name.into()))
+                .collect(),
+        }
+    }
+}
+
+impl From<HashMap<u32, String>> for ClassRegistry {
+    fn from(names: HashMap<u32, String>) -> Self {
+        Self { names }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_resolves_named_classes() {
+        let mut registry = ClassRegistry::from_iter([(0, "person"), (2, "car")]);
+        registry.insert(2, "vehicle");
+
+        assert_eq!(registry.name(0), Some("person"));
+        assert_eq!(registry.name(1), None);
+        assert_eq!(registry.name(2), Some("vehicle"));
+        assert_eq!(registry.len(), 2);
+    }
+}
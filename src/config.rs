@@ -0,0 +1,377 @@
+use std::collections::HashMap;
+
+use crate::{
+    associate::{AssociationMetric, MatchingStrategy},
+    bbox::BBox,
+};
+
+/// The 95% quantile of the chi-squared distribution with 4 degrees of freedom, the usual
+/// mahalanobis_gate for the 4 dimensional box measurements.
+pub const CHI_SQUARED_95_4_DOF: f64 = 9.4877;
+
+/// Lifecycle parameters overriding the global ones for a single class.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClassParams {
+    /// The maximum number of updates a tracker can have without new associations to detections.
+    pub max_age: u32,
+    /// The minimum number of consecutive associations a track needs to be returned.
+    pub min_hit_streak: u32,
+}
+
+/// Linear schedule that relaxes the IoU threshold of a track for each update it has
+/// been coasting without an association.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IouThresholdSchedule {
+    /// The amount the threshold is lowered by for each missed update.
+    pub slope: f64,
+    /// The lowest threshold the schedule relaxes to.
+    pub min_threshold: f64,
+}
+
+impl Default for IouThresholdSchedule {
+    fn default() -> Self {
+        Self {
+            slope: 0.05,
+            min_threshold: 0.1,
+        }
+    }
+}
+
+impl IouThresholdSchedule {
+    /// Returns the threshold for a track with the given time_since_update, counted after
+    /// the prediction of the current update, so a track associated in the previous update
+    /// has a time_since_update of 1 and keeps the iou_threshold. Never raises the threshold,
+    /// so a negative slope keeps the iou_threshold as well.
+    pub fn threshold(&self, iou_threshold: f64, time_since_update: u32) -> f64 {
+        let missed_updates = time_since_update.saturating_sub(1) as f64;
+        (iou_threshold - self.slope * missed_updates)
+            .max(self.min_threshold.min(iou_threshold))
+            .min(iou_threshold)
+    }
+}
+
+/// How detections with a degenerate bbox, i.e. a zero width or height or a non-finite
+/// coordinate, are handled before association.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DegenerateBBoxPolicy {
+    /// The detections are used as they are.
+    #[default]
+    Keep,
+    /// The detections are dropped before association and counted.
+    Reject,
+    /// The bboxes are grown around their center to at least the given width and height.
+    /// Detections with non-finite coordinates cannot be grown and are rejected.
+    MinSize(f64),
+}
+
+/// Configuration of the OC-SORT tracker.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OCSortConfig {
+    /// The maximum number of updates a tracker can have without new associations to detections.
+    pub max_age: u32,
+    /// The maximum time in seconds a tracker can go without new associations to detections.
+    /// Must be set together with fps, and replaces max_age with the number of frames in this
+    /// time, rounded to the nearest frame. Must be positive and finite.
+    pub max_age_seconds: Option<f64>,
+    /// The frame rate of the video, used to convert max_age_seconds to frames. Must be set
+    /// together with max_age_seconds, and be positive and finite.
+    pub fps: Option<f64>,
+    /// The minimum association score needed for an association. Compared against the raw
+    /// value of the association_metric, in [0, 1] for IoU and in [-1, 1] for GIoU, DIoU and
+    /// CIoU, and likewise for the stage thresholds below.
+    pub iou_threshold: f64,
+    /// The minimum association score needed for an association in the BYTE stage.
+    /// None uses iou_threshold.
+    pub byte_iou_threshold: Option<f64>,
+    /// The minimum association score needed for an association in the Observation Centric
+    /// Recovery stage. None uses iou_threshold.
+    pub ocr_iou_threshold: Option<f64>,
+    /// The time lag used for speed direction calculations.
+    pub delta_t: u32,
+    /// Score threshold used to divide detections to high and low sets in BYTE association.
+    pub score_threshold: f64,
+    /// The minimum score of an unmatched detection to start a new track. None uses
+    /// score_threshold, so only high score detections start tracks. Detections scoring
+    /// between the two thresholds can still be associated to existing tracks.
+    pub init_threshold: Option<f64>,
+    /// The minimum number of consecutive associations a track needs to be returned.
+    pub min_hit_streak: u32,
+    /// The number of updates during which no tracks are returned, e.g. while the camera
+    /// adjusts its focus and exposure. The tracks are still created and updated.
+    pub warmup_frames: u32,
+    /// Detections with a smaller bbox area are dropped before association. Dropped
+    /// detections neither match existing tracks nor spawn new ones.
+    pub min_area: Option<f64>,
+    /// Detections with a larger bbox area are dropped before association.
+    pub max_area: Option<f64>,
+    /// Detections with a smaller width / height aspect ratio are dropped before association.
+    pub min_aspect: Option<f64>,
+    /// Detections with a larger width / height aspect ratio are dropped before association.
+    pub max_aspect: Option<f64>,
+    /// How detections with a zero width or height or a non-finite coordinate are handled.
+    /// Rejected detections are counted by OCSort::take_rejected_count.
+    pub degenerate_bbox_policy: DegenerateBBoxPolicy,
+    /// When set, detections overlapping a higher score detection of the same class with an
+    /// IoU above this threshold are suppressed before association (non-maximum suppression).
+    /// With class_agnostic, detections of any class suppress each other.
+    pub nms_iou_threshold: Option<f64>,
+    /// Regions of the frame, e.g. the frame edges or known static occlusions, where the
+    /// detector is unreliable. Detections whose center falls inside any of the regions are
+    /// dropped before association.
+    pub ignore_regions: Vec<BBox>,
+    /// When set, detections with at least this fraction of their area inside an ignore
+    /// region are dropped as well, even if their center is outside it.
+    pub ignore_region_overlap: Option<f64>,
+    /// Whether tracks that missed the previous update and whose predicted bbox has its
+    /// center inside an ignore region are removed like expired tracks.
+    pub remove_coasting_in_ignore_regions: bool,
+    /// When set, relaxes the IoU threshold of each stage for coasting tracks, which have a
+    /// higher positional uncertainty. None uses the same threshold for every track.
+    pub iou_threshold_schedule: Option<IouThresholdSchedule>,
+    /// The maximum number of updates a tentative tracker can have without new associations.
+    /// None uses max_age for tentative trackers as well.
+    pub max_age_tentative: Option<u32>,
+    /// The bounding box similarity used to build the association cost matrix.
+    pub association_metric: AssociationMetric,
+    /// The algorithm used to solve the assignment between detections and trackers.
+    pub matching_strategy: MatchingStrategy,
+    /// The factor the association costs are multiplied with before rounding them to the
+    /// integers the assignment solver works on. The IoU and every other cost term are
+    /// resolved to 1 / cost_multiplier, so larger values separate nearly equal candidates.
+    /// The costs must stay far below the i64::MAX / 16 cell limit, which leaves ample
+    /// headroom up to about 1e12 with the class mismatch penalty of 100. Must be positive
+    /// and finite.
+    pub cost_multiplier: f64,
+    /// The weight of the observation centric momentum cost (vdc_weight in OC-SORT).
+    pub inertia_weight: f64,
+    /// Whether the speed direction used by the momentum cost is the direction of a least
+    /// squares line fitted over the centers of the last delta_t + 1 observations of a track
+    /// and the new one, instead of the direction from the observation delta_t steps back.
+    /// The fit is more robust to noisy detections.
+    pub fit_speed_direction: bool,
+    /// The weight of the |ln(detection area / tracker area)| cost penalizing scale changes.
+    /// Disabled with 0.
+    pub area_ratio_weight: f64,
+    /// Whether association ignores classes entirely and is driven by geometry and motion only.
+    /// Tracks report the class of their most recent detection.
+    pub class_agnostic: bool,
+    /// Whether class mismatches are given an effectively infinite cost instead of a large penalty.
+    /// Mismatching pairs are never matched either way: a pair the solver still picks is
+    /// rejected as an invalid match and both sides are left unmatched.
+    pub class_gating: bool,
+    /// When set, pairs whose squared Mahalanobis distance between the detection and the
+    /// predicted measurement of the track exceeds this value are never matched in the
+    /// primary and BYTE stages, e.g. CHI_SQUARED_95_4_DOF. The distance uses the innovation
+    /// covariance of the Kalman Filter, so the gate widens while a track is coasting.
+    pub mahalanobis_gate: Option<f64>,
+    /// Whether pairs where the detection and the track carry the same external id are
+    /// favored by one full IoU in the association cost, and pairs with different external
+    /// ids penalized by the same amount. Pairs still need to pass the IoU threshold.
+    pub use_ext_ids: bool,
+    /// The weight of the cosine distance between the detection and track embeddings in the
+    /// association cost of the primary stage. Pairs without both embeddings get no
+    /// appearance cost. Disabled with 0.
+    pub appearance_weight: f64,
+    /// The weight of the previous embedding in the exponential moving average of a track's
+    /// embedding.
+    pub embedding_momentum: f32,
+    /// The factor the score of a track is multiplied with for each update without an association.
+    /// Must be in [0, 1].
+    pub score_decay: f64,
+    /// The factor the velocity of a track is multiplied with for each prediction after an
+    /// update without an association, so coasting tracks slow down instead of drifting
+    /// arbitrarily far during long occlusions. 1 disables the damping. Must be in [0, 1].
+    /// The damping is part of the transition, so it shrinks the velocity covariance as well.
+    pub velocity_damping: f64,
+    /// The diagonal of the Kalman Filter process noise covariance Q over the state
+    /// [cx, cy, area, aspect ratio, vx, vy, v_area].
+    pub process_noise: [f64; 7],
+    /// The diagonal of the Kalman Filter measurement noise covariance R over the
+    /// observation [cx, cy, area, aspect ratio].
+    pub measurement_noise: [f64; 4],
+    /// The diagonal of the initial Kalman Filter state covariance P of a new tracker.
+    /// The velocity entries are large by default since a new track has no motion estimate.
+    pub initial_covariance: [f64; 7],
+    /// Whether the measurement noise is scaled inversely with the detection score,
+    /// so that high confidence detections are trusted more.
+    pub score_weighted_noise: bool,
+    /// Whether the trackers keep all their observations instead of only the last delta_t + 1.
+    /// Only the last delta_t + 1 observations are used for the speed direction.
+    pub keep_full_history: bool,
+    /// The maximum number of observations kept per tracker when keep_full_history is enabled.
+    /// None keeps every observation. Caps below delta_t + 1 are raised to delta_t + 1, so the
+    /// speed direction always has its full window.
+    pub max_history: Option<usize>,
+    /// Per class overrides of max_age and min_hit_streak. Classes not listed use the global values.
+    pub class_params: HashMap<u32, ClassParams>,
+    /// The (width, height) of the frame. When set, the bboxes of the returned tracks
+    /// are clamped inside the frame.
+    pub frame_size: Option<(f64, f64)>,
+}
+
+impl Default for OCSortConfig {
+    fn default() -> Self {
+        Self {
+            max_age: 30,
+            max_age_seconds: None,
+            fps: None,
+            iou_threshold: 0.3,
+            byte_iou_threshold: None,
+            ocr_iou_threshold: None,
+            delta_t: 3,
+            score_threshold: 0.6,
+            init_threshold: None,
+            min_hit_streak: 3,
+            warmup_frames: 0,
+            min_area: None,
+            max_area: None,
+            min_aspect: None,
+            max_aspect: None,
+            degenerate_bbox_policy: DegenerateBBoxPolicy::default(),
+            nms_iou_threshold: None,
+            ignore_regions: Vec::new(),
+            ignore_region_overlap: None,
+            remove_coasting_in_ignore_regions: false,
+            iou_threshold_schedule: None,
+            max_age_tentative: None,
+            association_metric: AssociationMetric::default(),
+            matching_strategy: MatchingStrategy::default(),
+            cost_multiplier: 10000.0,
+            inertia_weight: 0.2,
+            fit_speed_direction: false,
+            area_ratio_weight: 0.0,
+            class_agnostic: false,
+            class_gating: false,
+            mahalanobis_gate: None,
+            use_ext_ids: false,
+            appearance_weight: 0.0,
+            embedding_momentum: 0.9,
+            score_decay: 0.9,
+            velocity_damping: 1.0,
+            process_noise: [1.0, 1.0, 1.0, 1.0, 0.01, 0.01, 0.0001],
+            measurement_noise: [1.0, 1.0, 10.0, 10.0],
+            initial_covariance: [10.0, 10.0, 10.0, 10.0, 10000.0, 10000.0, 10000.0],
+            score_weighted_noise: false,
+            keep_full_history: false,
+            max_history: None,
+            class_params: HashMap::new(),
+            frame_size: None,
+        }
+    }
+}
+
+impl OCSortConfig {
+    /// Checks the parameters that would otherwise break association silently.
+    ///
+    /// ## Panics
+    /// Panics if
+    ///  - cost_multiplier is zero, negative or not finite.
+    ///  - velocity_damping or score_decay is not in [0, 1].
+    ///  - only one of max_age_seconds and fps is set, either is zero, negative or not
+    ///    finite, or their product does not fit in a u32 number of frames.
+    pub(crate) fn validate(&self) {
+        assert!(
+            self.cost_multiplier.is_finite() && self.cost_multiplier > 0.0,
+            "cost_multiplier must be positive and finite, got {}",
+            self.cost_multiplier
+        );
+        assert!(
+            (0.0..=1.0).contains(&self.velocity_damping),
+            "velocity_damping must be in [0, 1], got {}",
+            self.velocity_damping
+        );
+        assert!(
+            (0.0..=1.0).contains(&self.score_decay),
+            "score_decay must be in [0, 1], got {}",
+            self.score_decay
+        );
+        match (self.max_age_seconds, self.fps) {
+            (Some(seconds), Some(fps)) => {
+                assert!(
+                    seconds.is_finite() && seconds > 0.0,
+                    "max_age_seconds must be positive and finite, got {seconds}"
+                );
+                assert!(
+                    fps.is_finite() && fps > 0.0,
+                    "fps must be positive and finite, got {fps}"
+                );
+                assert!(
+                    (seconds * fps).round() <= f64::from(u32::MAX),
+                    "max_age_seconds * fps must fit in a u32 number of frames, got {}",
+                    seconds * fps
+                );
+            }
+            (None, None) => {}
+            _ => panic!("max_age_seconds and fps must be set together"),
+        }
+    }
+
+    /// Returns true if the bbox passes the min_area, max_area, min_aspect and max_aspect
+    /// filters.
+    pub fn accepts_bbox(&self, bbox: &BBox) -> bool {
+        let area = bbox.area();
+        let aspect = bbox.width() / bbox.height();
+
+        self.min_area.is_none_or(|min_area| area >= min_area)
+            && self.max_area.is_none_or(|max_area| area <= max_area)
+            && self
+                .min_aspect
+                .is_none_or(|min_aspect| aspect >= min_aspect)
+            && self
+                .max_aspect
+                .is_none_or(|max_aspect| aspect <= max_aspect)
+    }
+
+    /// Returns true if the degenerate_bbox_policy rejects the bbox. Assumes that bboxes
+    /// have already been grown with the MinSize policy.
+    pub fn rejects_degenerate(&self, bbox: &BBox) -> bool {
+        match self.degenerate_bbox_policy {
+            DegenerateBBoxPolicy::Keep => false,
+            DegenerateBBoxPolicy::Reject | DegenerateBBoxPolicy::MinSize(_) => bbox.is_degenerate(),
+        }
+    }
+
+    /// Returns true if the center of the bbox is inside one of the ignore_regions, or if at
+    /// least ignore_region_overlap of its area is inside one of them.
+    pub fn in_ignore_region(&self, bbox: &BBox) -> bool {
+        let (cx, cy) = bbox.center();
+        let area = bbox.area();
+
+        self.ignore_regions.iter().any(|region| {
+            region.contains_point(cx, cy)
+                || self.ignore_region_overlap.is_some_and(|overlap| {
+                    area > 0.0
+                        && region
+                            .intersection(bbox)
+                            .is_some_and(|intersection| intersection.area() / area >= overlap)
+                })
+        })
+    }
+
+    /// Returns the global max_age in frames, converted from max_age_seconds if it is set
+    /// together with fps.
+    pub fn max_age_frames(&self) -> u32 {
+        match (self.max_age_seconds, self.fps) {
+            (Some(seconds), Some(fps)) => (seconds * fps).round() as u32,
+            _ => self.max_age,
+        }
+    }
+
+    /// Returns the max_age of the given class.
+    pub fn max_age_of(&self, class: u32) -> u32 {
+        self.class_params
+            .get(&class)
+            .map_or(self.max_age_frames(), |params| params.max_age)
+    }
+
+    /// Returns the min_hit_streak of the given class.
+    pub fn min_hit_streak_of(&self, class: u32) -> u32 {
+        self.class_params
+            .get(&class)
+            .map_or(self.min_hit_streak, |params| params.min_hit_streak)
+    }
+}
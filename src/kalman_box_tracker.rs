@@ -1,21 +1,39 @@
-use std::{
-    collections::VecDeque,
-    sync::atomic::{AtomicU32, Ordering},
-};
+use std::collections::VecDeque;
 
 use kfilter::{
-    Kalman1M, KalmanFilter, KalmanPredict, measurement::LinearMeasurement,
-    system::LinearNoInputSystem,
+    Kalman, KalmanFilter, KalmanPredict, KalmanUpdate,
+    measurement::LinearMeasurement,
+    system::{LinearNoInputSystem, System},
 };
 use nalgebra::{SMatrix, SVector};
 
-use crate::bbox::BBox;
+use crate::{
+    bbox::BBox, class_registry::ClassRegistry, config::OCSortConfig, mask::RleMask,
+    oc_sort_tracker::Detection,
+};
+
+// Every tracker is created from an observation and observations are only dropped
+// to make room for new ones, so the observation buffer is never empty.
+const EMPTY_OBSERVATIONS: &str =
+    "tracker invariant violated: a tracker always has at least one observation";
 
+#[derive(Clone, Copy)]
 struct Observation {
     time_step: u32,
     bbox: BBox,
 }
 
+/// The lifecycle status of a tracker.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TrackStatus {
+    /// The tracker has not yet reached min_hit_streak consecutive associations.
+    #[default]
+    Tentative,
+    /// The tracker has reached min_hit_streak consecutive associations at least once.
+    Confirmed,
+}
+
 /// Represents a tracked object.
 #[derive(Debug)]
 pub struct Track {
@@ -25,70 +43,174 @@ pub struct Track {
     pub bbox: BBox,
     /// The class id of the object.
     pub class: u32,
+    /// The age of the tracked object in time steps.
+    pub age: u32,
+    /// The number of updates since the object was last associated to a detection.
+    pub time_since_update: u32,
+    /// The score of the last associated detection, decayed for each missed update.
+    pub score: f64,
+    /// The standard deviations of the estimated center x and center y of the object.
+    pub uncertainty: (f64, f64),
+    /// The index of the update in which the object was last associated to a detection.
+    pub last_update_frame: u64,
+    /// The number of consecutive associations of the object.
+    pub hit_streak: u32,
+    /// Whether the hit streak of the object has reached the min_hit_streak of its class at
+    /// least once. Stays true after a missed update breaks the streak.
+    pub confirmed: bool,
+}
+
+impl Track {
+    /// Returns the name of the class of the object in the registry, if it has one.
+    pub fn class_name<'a>(&self, registry: &'a ClassRegistry) -> Option<&'a str> {
+        registry.name(self.class)
+    }
+}
+
+/// Snapshot of the full state of a KalmanBoxTracker.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrackerState {
+    /// The id of the tracker.
+    pub id: u32,
+    /// The class id of the object.
+    pub class: u32,
+    /// The age of the tracked object in time steps.
+    pub age: u32,
+    /// The time lag used for speed direction calculations.
+    pub delta_t: u32,
+    /// The number of consecutive associations.
+    pub hit_streak: u32,
+    /// The lifecycle status of the tracker.
+    pub status: TrackStatus,
+    /// Time since last association.
+    pub time_since_update: u32,
+    /// The time elapsed since the last observation, the sum of the dt of the predictions.
+    pub time_since_observation: f64,
+    /// The index of the update in which the object was last associated to a detection.
+    pub last_update_frame: u64,
+    /// The score of the last associated detection.
+    pub score: f64,
+    /// The last external id seen in an associated detection.
+    pub ext_id: Option<u64>,
+    /// The embedding of the object.
+    pub embedding: Option<Vec<f32>>,
+    /// The segmentation mask of the last associated detection.
+    pub mask: Option<RleMask>,
+    /// The direction the object is going to.
+    pub speed_direction: [f64; 2],
+    /// The Kalman Filter state mean.
+    pub mean: [f64; 7],
+    /// The Kalman Filter state covariance in column-major order.
+    pub covariance: [[f64; 7]; 7],
+    /// The Kalman Filter state mean and covariance predicted right after the last observation,
+    /// used to re-update the filter when a missed object is found again.
+    pub frozen_state: Option<([f64; 7], [[f64; 7]; 7])>,
+    /// The previous associations made as (time_step, bbox) pairs, oldest first.
+    pub observations: Vec<(u32, BBox)>,
 }
 
 /// Struct that keeps track of an object with the use of a Kalman Filter.
 pub struct KalmanBoxTracker {
     /// The age of the tracked object in time steps.
     age: u32,
+    /// The current bounding box estimate, kept in sync with the Kalman Filter state.
+    bbox: BBox,
     /// The class id of the object.
-    pub class: u32,
+    pub(crate) class: u32,
     /// The time lag used for speed direction calculations.
     delta_t: u32,
+    /// The embedding of the object, an exponential moving average of the embeddings of the
+    /// associated detections normalized to unit length.
+    pub(crate) embedding: Option<Vec<f32>>,
+    /// The last external id seen in an associated detection.
+    pub(crate) ext_id: Option<u64>,
+    /// The segmentation mask of the last associated detection that had one.
+    pub(crate) mask: Option<RleMask>,
     /// The number of consecutive associations.
-    pub hit_streak: u32,
+    pub(crate) hit_streak: u32,
     /// The id of the tracker.
     id: u32,
+    /// The factor the velocity is multiplied with for each prediction while coasting.
+    velocity_damping: f64,
+    /// The index of the OCSort update in which the object was last associated to a
+    /// detection, or created.
+    pub(crate) last_update_frame: u64,
+    /// The Kalman Filter state mean and covariance predicted for the update right after the
+    /// last observation. Restored to re-update the filter when the object is found again.
+    frozen_state: Option<(SVector<f64, 7>, SMatrix<f64, 7, 7>)>,
     /// The Kalman Filter used to track the object.
-    kalman_filter:
-        Kalman1M<f64, 7, 0, 4, LinearNoInputSystem<f64, 7>, LinearMeasurement<f64, 7, 4>>,
+    kalman_filter: Kalman<f64, 7, 0, LinearNoInputSystem<f64, 7>>,
+    /// The measurement used to update the Kalman Filter.
+    measurement: LinearMeasurement<f64, 7, 4>,
     /// The previous associations made.
     prev_observations: VecDeque<Observation>,
+    /// The score of the last associated detection.
+    pub(crate) score: f64,
     /// The direction the object is going to.
-    pub speed_direction: SVector<f64, 2>,
+    pub(crate) speed_direction: SVector<f64, 2>,
+    /// The lifecycle status of the tracker.
+    status: TrackStatus,
     /// Time since last association.
-    pub time_since_update: u32,
+    pub(crate) time_since_update: u32,
+    /// The time elapsed since the last observation, the sum of the dt of the predictions.
+    time_since_observation: f64,
 }
 
-static ID_COUNTER: AtomicU32 = AtomicU32::new(0);
-
 impl KalmanBoxTracker {
-    /// Creates a new tracker for a given bounding box.
+    /// Creates a new tracker for a given bounding box with the default noise parameters.
     ///
     /// ## Args:
     ///  - bbox: The bounding box of the object.
     ///  - class: The class id of the object.
     ///  - delta_t: The time lag used for speed direction calculations.
+    ///  - id: The unique id of the tracked object.
+    #[cfg(test)]
+    pub fn new(bbox: BBox, class: u32, delta_t: u32, id: u32) -> Self {
+        Self::with_config(
+            bbox,
+            class,
+            id,
+            &OCSortConfig {
+                delta_t,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Creates a new tracker for a given bounding box.
+    ///
+    /// ## Args:
+    ///  - bbox: The bounding box of the object.
+    ///  - class: The class id of the object.
+    ///  - id: The unique id of the tracked object.
+    ///  - config: The configuration containing delta_t, velocity_damping and the Kalman Filter
+    ///    noise parameters.
     #[allow(non_snake_case)]
-    pub fn new(bbox: BBox, class: u32, delta_t: u32) -> Self {
-        let mut F = SMatrix::<f64, 7, 7>::identity();
-        F[(0, 4)] = 1.0;
-        F[(1, 5)] = 1.0;
-        F[(2, 6)] = 1.0;
-        let Q_diag = SVector::<f64, 7>::from_vec(vec![1.0, 1.0, 1.0, 1.0, 0.01, 0.01, 0.0001]);
-        let Q = SMatrix::<f64, 7, 7>::from_diagonal(&Q_diag);
+    pub fn with_config(bbox: BBox, class: u32, id: u32, config: &OCSortConfig) -> Self {
+        let delta_t = config.delta_t;
+        let F = transition_matrix(1.0);
+        let Q = SMatrix::<f64, 7, 7>::from_diagonal(&SVector::from(config.process_noise));
         let mut x_initial = SVector::<f64, 7>::zeros();
         x_initial
             .fixed_rows_mut::<4>(0)
             .copy_from(&bbox.to_observation_vector());
         let system = LinearNoInputSystem::new(F, Q, x_initial);
 
-        let P_diag =
-            SVector::<f64, 7>::from_vec(vec![10.0, 10.0, 10.0, 10.0, 10000.0, 10000.0, 10000.0]);
-        let P = SMatrix::<f64, 7, 7>::from_diagonal(&P_diag);
+        let P = SMatrix::<f64, 7, 7>::from_diagonal(&SVector::from(config.initial_covariance));
 
         let H = SMatrix::<f64, 4, 7>::identity();
-        let R_diag = SVector::<f64, 4>::new(1.0, 1.0, 10.0, 10.0);
-        let R = SMatrix::from_diagonal(&R_diag);
+        let R = SMatrix::from_diagonal(&SVector::from(config.measurement_noise));
         let measurement = LinearMeasurement::new(H, R, bbox.to_observation_vector());
 
-        let kalman_filter = Kalman1M::new_custom(system, P, measurement);
+        let kalman_filter = Kalman::new_custom(system, P);
 
-        let id = ID_COUNTER.fetch_add(1, Ordering::Relaxed);
         let age: u32 = 0;
 
         Self {
             kalman_filter,
+            measurement,
+            bbox: BBox::from_state_vector(x_initial),
             id,
             prev_observations: VecDeque::from([Observation {
                 time_step: age,
@@ -97,87 +219,528 @@ impl KalmanBoxTracker {
             age,
             hit_streak: 1,
             delta_t,
+            velocity_damping: config.velocity_damping,
             speed_direction: SVector::<f64, 2>::zeros(),
             class,
+            embedding: None,
+            ext_id: None,
+            mask: None,
             time_since_update: 0,
+            time_since_observation: 0.0,
+            last_update_frame: 0,
+            frozen_state: None,
+            score: 0.0,
+            status: TrackStatus::Tentative,
+        }
+    }
+
+    /// Restores a tracker from a snapshot created with save_state, using the noise
+    /// parameters of the given config.
+    pub fn from_state(state: TrackerState, config: &OCSortConfig) -> Self {
+        let (_, first_bbox) = state.observations.first().copied().unwrap_or_default();
+        let mut tracker = Self::with_config(
+            first_bbox,
+            state.class,
+            state.id,
+            &OCSortConfig {
+                delta_t: state.delta_t,
+                ..config.clone()
+            },
+        );
+
+        *tracker.kalman_filter.system_mut().state_mut() = SVector::from(state.mean);
+        *tracker.kalman_filter.covariance_mut() = SMatrix::from(state.covariance);
+        tracker.frozen_state = state
+            .frozen_state
+            .map(|(mean, covariance)| (SVector::from(mean), SMatrix::from(covariance)));
+        tracker.bbox = BBox::from_state_vector(*tracker.kalman_filter.state());
+        // Keep the observation created by new for states without observations.
+        if !state.observations.is_empty() {
+            tracker.prev_observations = state
+                .observations
+                .into_iter()
+                .map(|(time_step, bbox)| Observation { time_step, bbox })
+                .collect();
+        }
+        tracker.age = state.age;
+        tracker.hit_streak = state.hit_streak;
+        tracker.time_since_update = state.time_since_update;
+        tracker.time_since_observation = state.time_since_observation;
+        tracker.last_update_frame = state.last_update_frame;
+        tracker.score = state.score;
+        tracker.ext_id = state.ext_id;
+        tracker.embedding = state.embedding;
+        tracker.mask = state.mask;
+        tracker.status = state.status;
+        tracker.speed_direction = SVector::from(state.speed_direction);
+
+        tracker
+    }
+
+    /// Returns a snapshot of the full state of the tracker.
+    pub fn save_state(&self) -> TrackerState {
+        TrackerState {
+            id: self.id,
+            class: self.class,
+            age: self.age,
+            delta_t: self.delta_t,
+            hit_streak: self.hit_streak,
+            status: self.status,
+            time_since_update: self.time_since_update,
+            time_since_observation: self.time_since_observation,
+            last_update_frame: self.last_update_frame,
+            score: self.score,
+            ext_id: self.ext_id,
+            embedding: self.embedding.clone(),
+            mask: self.mask.clone(),
+            speed_direction: self.speed_direction.into(),
+            mean: (*self.kalman_filter.state()).into(),
+            covariance: (*self.kalman_filter.covariance()).into(),
+            frozen_state: self
+                .frozen_state
+                .map(|(mean, covariance)| (mean.into(), covariance.into())),
+            observations: self.observation_history(),
         }
     }
 
+    /// Returns the id of the tracker.
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the class id of the object.
+    pub fn class(&self) -> u32 {
+        self.class
+    }
+
+    /// Returns the number of consecutive associations.
+    pub fn hit_streak(&self) -> u32 {
+        self.hit_streak
+    }
+
+    /// Returns the number of updates since the last association.
+    pub fn time_since_update(&self) -> u32 {
+        self.time_since_update
+    }
+
+    /// Returns the index of the OCSort update in which the object was last associated to a
+    /// detection, or created.
+    pub fn last_update_frame(&self) -> u64 {
+        self.last_update_frame
+    }
+
+    /// Returns the score of the last associated detection.
+    pub fn score(&self) -> f64 {
+        self.score
+    }
+
+    /// Returns the last external id seen in an associated detection.
+    pub fn ext_id(&self) -> Option<u64> {
+        self.ext_id
+    }
+
+    /// Returns the embedding of the object.
+    pub fn embedding(&self) -> Option<&[f32]> {
+        self.embedding.as_deref()
+    }
+
+    /// Returns the segmentation mask of the last associated detection that had one.
+    pub fn mask(&self) -> Option<&RleMask> {
+        self.mask.as_ref()
+    }
+
+    /// Returns the unit direction the object is going to as [x, y], or zeros if it has no
+    /// speed direction.
+    pub fn speed_direction(&self) -> [f64; 2] {
+        self.speed_direction.into()
+    }
+
     /// Returns the bounding box of the last association made to a detection.
+    #[track_caller]
     pub fn get_last_observation(&self) -> &BBox {
-        self.prev_observations.back().map(|obs| &obs.bbox).unwrap()
+        &self.last_observation().bbox
+    }
+
+    /// Returns the previous associations as (time_step, bbox) pairs, oldest first.
+    ///
+    /// Unless keep_full_history is enabled in the config, only the last delta_t + 1 observations
+    /// are kept, so this is not the full trajectory of long lived tracks.
+    pub fn observation_history(&self) -> Vec<(u32, BBox)> {
+        self.prev_observations
+            .iter()
+            .map(|obs| (obs.time_step, obs.bbox))
+            .collect()
+    }
+
+    /// Returns the (time_step, bbox) pairs of the time steps missed between the last two
+    /// observations, oldest first. The boxes are interpolated linearly between the two
+    /// observations, i.e. they are the virtual observations used to re-update the Kalman
+    /// Filter when the object was found again.
+    ///
+    /// Returns an empty Vec if the tracker has fewer than two observations or the last two
+    /// observations are consecutive.
+    pub fn interpolated_gap(&self) -> Vec<(u32, BBox)> {
+        let mut observations = self.prev_observations.iter().rev();
+        let (Some(last), Some(previous)) = (observations.next(), observations.next()) else {
+            return Vec::new();
+        };
+        let steps_between = last.time_step - previous.time_step;
+
+        (1..steps_between)
+            .map(|t| {
+                (
+                    previous.time_step + t,
+                    interpolate_bbox(&previous.bbox, &last.bbox, t, steps_between),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns true if the tracker has a speed direction, i.e. it has been associated with
+    /// a detection since its creation and the object has moved between the observations.
+    /// The direction of a newly created tracker is the zero vector.
+    pub fn has_speed_direction(&self) -> bool {
+        self.speed_direction != SVector::<f64, 2>::zeros()
     }
 
     /// Returns the observation bounding box of the tracker that is closest to delta_t
     /// time steps away.
+    ///
+    /// Only the last delta_t + 1 observations are considered, which always include the
+    /// observation made exactly delta_t time steps ago if there is one.
+    #[track_caller]
     pub fn get_observation_dt_time_steps_away(&self) -> &BBox {
+        let window_start = self
+            .prev_observations
+            .len()
+            .saturating_sub(self.delta_t as usize + 1);
         self.prev_observations
-            .iter()
+            .range(window_start..)
             .min_by_key(|obs| {
                 obs.time_step
                     .abs_diff(self.age.saturating_sub(self.delta_t))
             })
             .map(|obs| &obs.bbox)
-            .unwrap()
+            .expect(EMPTY_OBSERVATIONS)
+    }
+
+    /// Returns the lifecycle status of the tracker.
+    pub fn status(&self) -> TrackStatus {
+        self.status
+    }
+
+    /// Promotes the tracker to confirmed once its hit streak reaches the min_hit_streak
+    /// of its class. Confirmed trackers stay confirmed.
+    pub(crate) fn update_status(&mut self, config: &OCSortConfig) {
+        if self.hit_streak >= config.min_hit_streak_of(self.class) {
+            self.status = TrackStatus::Confirmed;
+        }
     }
 
     /// Returns the tracker's current bounding box.
     pub fn get_bbox(&self) -> BBox {
-        BBox::from_state_vector(*self.kalman_filter.state())
+        self.bbox
     }
 
     /// Returns the Track representation of the currently tracked object.
+    ///
+    /// The score of the track is the score of the last associated detection without decay.
     pub fn get_state(&self) -> Track {
-        let bbox = BBox::from_state_vector(*self.kalman_filter.state());
         Track {
             id: self.id,
-            bbox,
+            bbox: self.bbox,
             class: self.class,
+            age: self.age,
+            time_since_update: self.time_since_update,
+            score: self.score,
+            uncertainty: {
+                let covariance = self.position_covariance();
+                (covariance[(0, 0)].sqrt(), covariance[(1, 1)].sqrt())
+            },
+            last_update_frame: self.last_update_frame,
+            hit_streak: self.hit_streak,
+            confirmed: self.status == TrackStatus::Confirmed,
         }
     }
 
+    /// Returns the squared Mahalanobis distance between the bounding box and the predicted
+    /// measurement of the tracker, using the innovation covariance with the measurement
+    /// noise of the config. Returns infinity if the covariance is not invertible.
+    #[allow(non_snake_case)]
+    pub fn mahalanobis_distance(&self, bbox: &BBox, config: &OCSortConfig) -> f64 {
+        let H = SMatrix::<f64, 4, 7>::identity();
+        let R = SMatrix::from_diagonal(&SVector::from(config.measurement_noise));
+        let S = H * self.kalman_filter.covariance() * H.transpose() + R;
+        let innovation = bbox.to_observation_vector() - H * self.kalman_filter.state();
+
+        S.try_inverse().map_or(f64::INFINITY, |S_inverse| {
+            (innovation.transpose() * S_inverse * innovation)[(0, 0)]
+        })
+    }
+
+    /// Returns the covariance of the estimated center x and center y of the object.
+    pub fn position_covariance(&self) -> SMatrix<f64, 2, 2> {
+        self.kalman_filter
+            .covariance()
+            .fixed_view::<2, 2>(0, 0)
+            .into_owned()
+    }
+
     /// Updates the state estimation of the tracked object with the bounding box from a detection.
-    pub fn update(&mut self, bbox: BBox) {
-        self.update_speed_direction(&bbox);
-        self.update_kalman_filter(&bbox.to_observation_vector());
-        self.add_bbox_to_observations(bbox);
+    ///
+    /// When score_weighted_noise is enabled in the config, the measurement noise is scaled
+    /// inversely with the detection score. The uncertainty of the detection, if any, further
+    /// scales the noise of each coordinate.
+    pub fn update(&mut self, detection: &Detection, config: &OCSortConfig) {
+        let bbox = detection.bbox;
+        let noise_scale = if config.score_weighted_noise {
+            1.0 / detection.score.clamp(f64::EPSILON, 1.0)
+        } else {
+            1.0
+        };
+        let mut measurement_noise = SVector::from(config.measurement_noise) * noise_scale;
+        if let Some(uncertainty) = detection.uncertainty {
+            measurement_noise.component_mul_assign(&SVector::from(uncertainty));
+        }
+        self.measurement.R = SMatrix::from_diagonal(&measurement_noise);
+
+        self.update_speed_direction(&bbox, config);
+        self.update_kalman_filter(&bbox);
+        self.bbox = BBox::from_state_vector(*self.kalman_filter.state());
+        self.add_bbox_to_observations(bbox, config);
         self.time_since_update = 0;
+        self.time_since_observation = 0.0;
         self.hit_streak += 1;
+        self.score = detection.score;
+        self.class = detection.class;
+        if detection.ext_id.is_some() {
+            self.ext_id = detection.ext_id;
+        }
+        if let Some(embedding) = &detection.embedding {
+            self.update_embedding(embedding, config.embedding_momentum);
+        }
+        if detection.mask.is_some() {
+            self.mask.clone_from(&detection.mask);
+        }
+        self.update_status(config);
+    }
+
+    /// Blends the embedding into the embedding of the tracker with an exponential moving
+    /// average. Replaces the embedding if the tracker has none or its length differs.
+    pub(crate) fn update_embedding(&mut self, embedding: &[f32], momentum: f32) {
+        let blended = match &mut self.embedding {
+            Some(current) if current.len() == embedding.len() => {
+                current
+                    .iter_mut()
+                    .zip(embedding)
+                    .for_each(|(current, &new)| {
+                        *current = momentum * *current + (1.0 - momentum) * new
+                    });
+                current
+            }
+            current => current.insert(embedding.to_vec()),
+        };
+
+        let norm = blended
+            .iter()
+            .map(|value| value * value)
+            .sum::<f32>()
+            .sqrt();
+        if norm > 0.0 {
+            blended.iter_mut().for_each(|value| *value /= norm);
+        }
+    }
+
+    /// Adds the observations of another tracker of the same object to the observations of
+    /// this tracker, e.g. when merging a track that was split into two ids. The time steps
+    /// of the other tracker are shifted to the age of this tracker, and observations older
+    /// than this tracker or at a time step this tracker already observed are dropped.
+    ///
+    /// The Kalman Filter state, speed direction and counters of this tracker are not changed.
+    pub(crate) fn merge_observations(&mut self, other: &KalmanBoxTracker, config: &OCSortConfig) {
+        let mut observations: Vec<Observation> = other
+            .prev_observations
+            .iter()
+            .filter_map(|obs| {
+                let frames_ago = other.age - obs.time_step;
+                let time_step = self.age.checked_sub(frames_ago)?;
+                Some(Observation {
+                    time_step,
+                    bbox: obs.bbox,
+                })
+            })
+            .filter(|obs| {
+                !self
+                    .prev_observations
+                    .iter()
+                    .any(|own| own.time_step == obs.time_step)
+            })
+            .collect();
+        observations.extend(self.prev_observations.drain(..));
+        observations.sort_by_key(|obs| obs.time_step);
+
+        let capacity = self.observation_capacity(config);
+        let skip = observations.len().saturating_sub(capacity);
+        self.prev_observations = observations.into_iter().skip(skip).collect();
     }
 
     /// Predicts the next state of the object. Returns the predicted bounding box.
+    #[cfg(test)]
     pub fn predict(&mut self) -> BBox {
-        self.age += 1;
-        if self.time_since_update > 0 {
+        self.predict_dt(1.0)
+    }
+
+    /// Predicts the state of the object dt time steps ahead by scaling the velocity terms
+    /// of the transition. Counts as a single update for the age, time_since_update and the
+    /// observation time steps of the tracker. Returns the predicted bounding box.
+    ///
+    /// ## Panics
+    /// Panics if dt is not positive and finite.
+    pub fn predict_dt(&mut self, dt: f64) -> BBox {
+        let coasting = self.time_since_update > 0;
+        if coasting {
             self.hit_streak = 0;
         }
         self.time_since_update += 1;
-        let state_vector = self.kalman_filter.predict();
+        self.advance(dt, coasting)
+    }
+
+    /// Predicts the state of the object dt time steps ahead like predict_dt, but without
+    /// counting a missed association: time_since_update and hit_streak are kept, so the
+    /// next predict_dt of an associated tracker does not break its hit streak. The age and
+    /// the observation time steps still advance, so the re-update after a gap spans these
+    /// steps as well. Returns the predicted bounding box.
+    ///
+    /// ## Panics
+    /// Panics if dt is not positive and finite.
+    pub(crate) fn predict_between_updates(&mut self, dt: f64) -> BBox {
+        self.advance(dt, self.time_since_update > 0)
+    }
+
+    fn advance(&mut self, dt: f64, coasting: bool) -> BBox {
+        assert!(
+            dt.is_finite() && dt > 0.0,
+            "dt must be positive and finite, got {dt}"
+        );
+        self.time_since_observation += dt;
+        let mut transition = transition_matrix(dt);
+        if coasting {
+            // Damping the velocity in the transition shrinks its covariance along with it.
+            transition
+                .fixed_columns_mut::<3>(4)
+                .scale_mut(self.velocity_damping);
+        }
+        self.kalman_filter.system_mut().set_transition(transition);
+        self.age += 1;
+        let state_vector = *self.kalman_filter.predict();
+        self.bbox = BBox::from_state_vector(state_vector);
+        // The re-update rolls back to the first prediction after the last observation.
+        if self.age == self.last_observation().time_step + 1 {
+            self.frozen_state = Some((state_vector, *self.kalman_filter.covariance()));
+        }
+
+        self.bbox
+    }
 
-        BBox::from_state_vector(*state_vector)
+    fn update_speed_direction(&mut self, bbox: &BBox, config: &OCSortConfig) {
+        self.speed_direction = if config.fit_speed_direction {
+            self.fitted_speed_direction(bbox)
+        } else {
+            bbox.speed_direction(self.get_observation_dt_time_steps_away())
+        };
     }
 
-    fn update_speed_direction(&mut self, bbox: &BBox) {
-        let prev_obs = self.get_observation_dt_time_steps_away();
-        self.speed_direction = bbox.speed_direction(&prev_obs);
+    /// Returns the unit direction of the least squares line fitted over the (time_step, center)
+    /// points of the last delta_t + 1 observations and the bbox observed at the current age.
+    /// Returns a zero vector if the direction cannot be determined.
+    fn fitted_speed_direction(&self, bbox: &BBox) -> SVector<f64, 2> {
+        let window_start = self
+            .prev_observations
+            .len()
+            .saturating_sub(self.delta_t as usize + 1);
+        let points: Vec<(f64, SVector<f64, 2>)> = self
+            .prev_observations
+            .range(window_start..)
+            .map(|obs| (obs.time_step, &obs.bbox))
+            .chain(std::iter::once((self.age, bbox)))
+            .map(|(time_step, bbox)| {
+                let (cx, cy) = bbox.center();
+                (time_step as f64, SVector::<f64, 2>::new(cx, cy))
+            })
+            .collect();
+
+        let count = points.len() as f64;
+        let mean_time = points.iter().map(|(time, _)| time).sum::<f64>() / count;
+        let mean_center = points
+            .iter()
+            .fold(SVector::<f64, 2>::zeros(), |sum, (_, center)| sum + center)
+            / count;
+        // The slope of the fit is this over the time variance, which is positive, so the
+        // covariance alone gives the direction.
+        let covariance = points
+            .iter()
+            .fold(SVector::<f64, 2>::zeros(), |sum, (time, center)| {
+                sum + (center - mean_center) * (time - mean_time)
+            });
+        let norm = covariance.norm();
+
+        if norm > 0.0 {
+            return covariance / norm;
+        }
+
+        SVector::<f64, 2>::zeros()
     }
 
-    fn update_kalman_filter(&mut self, z: &SVector<f64, 4>) {
-        let last_observation = self.prev_observations.back().unwrap();
+    /// Updates the Kalman Filter with the observation. If the object was missed since the
+    /// last observation, performs the Observation-centric Re-Update of OC-SORT: the filter
+    /// is rolled back to the state predicted right after the last observation and updated
+    /// along a virtual trajectory linearly interpolated between the two observations, so the
+    /// error accumulated while coasting is discarded. The virtual observations are assumed
+    /// to be evenly spaced in time over the time elapsed since the last observation.
+    fn update_kalman_filter(&mut self, bbox: &BBox) {
+        let last_observation = *self.last_observation();
         let steps_between = self.age - last_observation.time_step;
-        for t in 1..=steps_between {
-            let z_interpolated = (steps_between - t) as f64 / steps_between as f64
-                * last_observation.bbox.to_observation_vector()
-                + t as f64 / steps_between as f64 * z;
-            self.kalman_filter.update(z_interpolated);
-            if t < steps_between {
+        let frozen_state = self.frozen_state.take();
+        if let (true, Some((mean, covariance))) = (steps_between > 1, frozen_state) {
+            *self.kalman_filter.system_mut().state_mut() = mean;
+            *self.kalman_filter.covariance_mut() = covariance;
+            let step_dt = self.time_since_observation / steps_between as f64;
+            self.kalman_filter
+                .system_mut()
+                .set_transition(transition_matrix(step_dt));
+            for t in 1..steps_between {
+                self.measurement.z =
+                    interpolate_bbox(&last_observation.bbox, bbox, t, steps_between)
+                        .to_observation_vector();
+                self.kalman_filter.update(&self.measurement);
                 self.kalman_filter.predict();
             }
         }
+        self.measurement.z = bbox.to_observation_vector();
+        self.kalman_filter.update(&self.measurement);
+    }
+
+    #[track_caller]
+    fn last_observation(&self) -> &Observation {
+        self.prev_observations.back().expect(EMPTY_OBSERVATIONS)
     }
 
-    fn add_bbox_to_observations(&mut self, bbox: BBox) {
-        if self.prev_observations.len() >= self.delta_t as usize {
+    /// Returns the number of observations kept, never fewer than the delta_t + 1 used for
+    /// the speed direction.
+    fn observation_capacity(&self, config: &OCSortConfig) -> usize {
+        let momentum_window = self.delta_t as usize + 1;
+        if config.keep_full_history {
+            config
+                .max_history
+                .map_or(usize::MAX, |max_history| max_history.max(momentum_window))
+        } else {
+            momentum_window
+        }
+    }
+
+    fn add_bbox_to_observations(&mut self, bbox: BBox, config: &OCSortConfig) {
+        let capacity = self.observation_capacity(config);
+        while self.prev_observations.len() >= capacity {
             self.prev_observations.pop_front();
         }
         self.prev_observations.push_back(Observation {
@@ -187,6 +750,30 @@ impl KalmanBoxTracker {
     }
 }
 
+/// Linearly interpolates the corners of the bounding boxes, t steps of steps_between from
+/// last_bbox towards bbox. Equivalent to interpolating the center, width and height.
+fn interpolate_bbox(last_bbox: &BBox, bbox: &BBox, t: u32, steps_between: u32) -> BBox {
+    let weight = t as f64 / steps_between as f64;
+    let lerp = |from: f64, to: f64| from + weight * (to - from);
+
+    BBox::new(
+        lerp(last_bbox.x_1, bbox.x_1),
+        lerp(last_bbox.y_1, bbox.y_1),
+        lerp(last_bbox.x_2, bbox.x_2),
+        lerp(last_bbox.y_2, bbox.y_2),
+    )
+}
+
+/// Returns the constant velocity transition matrix for a time step of dt.
+fn transition_matrix(dt: f64) -> SMatrix<f64, 7, 7> {
+    let mut transition = SMatrix::<f64, 7, 7>::identity();
+    transition[(0, 4)] = dt;
+    transition[(1, 5)] = dt;
+    transition[(2, 6)] = dt;
+
+    transition
+}
+
 impl AsRef<KalmanBoxTracker> for KalmanBoxTracker {
     fn as_ref(&self) -> &KalmanBoxTracker {
         self
@@ -197,10 +784,15 @@ impl AsRef<KalmanBoxTracker> for KalmanBoxTracker {
 mod tests {
     use super::*;
 
+    /// Returns a 10 x 10 detection at x with a score of 0.9.
+    fn detection_at(x: f64, class: u32) -> Detection {
+        Detection::new(BBox::new(x, 0.0, x + 10.0, 10.0), class).with_score(0.9)
+    }
+
     #[test]
     fn test_new_succeeds() {
         let bbox = BBox::new(1.0, 1.0, 2.0, 2.0);
-        KalmanBoxTracker::new(bbox, 3, 0);
+        KalmanBoxTracker::new(bbox, 3, 0, 0);
     }
 
     #[test]
@@ -208,9 +800,12 @@ mod tests {
         let bbox_1 = BBox::new(0.0, 0.0, 1.0, 1.0);
         let bbox_2 = BBox::new(0.5, 0.0, 1.5, 1.0);
 
-        let mut tracker = KalmanBoxTracker::new(bbox_1, 1, 1);
+        let mut tracker = KalmanBoxTracker::new(bbox_1, 1, 1, 0);
         tracker.predict();
-        tracker.update(bbox_2);
+        tracker.update(
+            &Detection::new(bbox_2, 1).with_score(0.9),
+            &OCSortConfig::default(),
+        );
 
         let bbox_3 = tracker.predict();
         let tolerance = 0.01;
@@ -220,4 +815,450 @@ mod tests {
         assert!((bbox_3.x_2 - 2.0).abs() < tolerance);
         assert!((bbox_3.y_2 - 1.0).abs() < tolerance);
     }
+
+    #[test]
+    fn test_get_state_reports_age_and_time_since_update() {
+        let mut tracker = KalmanBoxTracker::new(BBox::new(0.0, 0.0, 1.0, 1.0), 1, 3, 0);
+        tracker.predict();
+        tracker.update(
+            &Detection::new(BBox::new(0.5, 0.0, 1.5, 1.0), 1).with_score(0.9),
+            &OCSortConfig::default(),
+        );
+        let track = tracker.get_state();
+        assert_eq!(track.age, 1);
+        assert_eq!(track.time_since_update, 0);
+
+        tracker.predict();
+        tracker.predict();
+        let track = tracker.get_state();
+        assert_eq!(track.age, 3);
+        assert_eq!(track.time_since_update, 2);
+    }
+
+    #[test]
+    fn test_keep_full_history_grows_beyond_delta_t() {
+        let full_history_config = OCSortConfig {
+            keep_full_history: true,
+            ..Default::default()
+        };
+        let capped_history_config = OCSortConfig {
+            keep_full_history: true,
+            max_history: Some(4),
+            ..Default::default()
+        };
+        // Caps below delta_t + 1 would shorten the window of the speed direction.
+        let short_history_config = OCSortConfig {
+            keep_full_history: true,
+            max_history: Some(2),
+            ..Default::default()
+        };
+        let bbox = BBox::new(0.0, 0.0, 10.0, 10.0);
+        let mut trackers = [
+            (
+                KalmanBoxTracker::new(bbox, 1, 3, 0),
+                OCSortConfig::default(),
+            ),
+            (KalmanBoxTracker::new(bbox, 1, 3, 1), full_history_config),
+            (KalmanBoxTracker::new(bbox, 1, 3, 2), capped_history_config),
+            (KalmanBoxTracker::new(bbox, 1, 3, 3), short_history_config),
+        ];
+
+        for i in 1..=6 {
+            for (tracker, config) in trackers.iter_mut() {
+                tracker.predict();
+                tracker.update(
+                    &Detection::new(
+                        BBox::new(i as f64, 0.0, i as f64 + 10.0, 10.0 + i as f64 * 0.5),
+                        1,
+                    )
+                    .with_score(0.9),
+                    config,
+                );
+            }
+        }
+
+        let history_lengths: Vec<usize> = trackers
+            .iter()
+            .map(|(tracker, _)| tracker.observation_history().len())
+            .collect();
+        assert_eq!(history_lengths, vec![4, 7, 4, 4]);
+        // The speed direction only uses the last delta_t + 1 observations.
+        for (tracker, _) in &trackers[1..] {
+            assert_eq!(tracker.speed_direction, trackers[0].0.speed_direction);
+        }
+    }
+
+    #[test]
+    fn test_observation_dt_time_steps_away_is_delta_t_back() {
+        let delta_t = 3;
+        let bboxes: Vec<BBox> = (0..=delta_t)
+            .map(|i| BBox::new(i as f64, 0.0, i as f64 + 10.0, 10.0))
+            .collect();
+        let mut tracker = KalmanBoxTracker::new(bboxes[0], 1, delta_t, 0);
+        for &bbox in &bboxes[1..] {
+            tracker.predict();
+            tracker.update(
+                &Detection::new(bbox, 1).with_score(0.9),
+                &OCSortConfig::default(),
+            );
+        }
+
+        // The observation of age 0 is delta_t time steps back from age delta_t.
+        let observation = tracker.get_observation_dt_time_steps_away();
+        assert_eq!(observation, &bboxes[0]);
+
+        tracker.predict();
+        let observation = tracker.get_observation_dt_time_steps_away();
+        assert_eq!(observation, &bboxes[1]);
+    }
+
+    #[test]
+    fn test_tracker_is_confirmed_after_min_hit_streak_hits() {
+        let config = OCSortConfig::default();
+        let mut tracker = KalmanBoxTracker::new(BBox::new(0.0, 0.0, 10.0, 10.0), 1, 3, 0);
+        tracker.update_status(&config);
+        assert_eq!(tracker.status(), TrackStatus::Tentative);
+
+        let mut statuses = Vec::new();
+        for i in 1..=3 {
+            tracker.predict();
+            tracker.update(
+                &Detection::new(BBox::new(i as f64, 0.0, i as f64 + 10.0, 10.0), 1).with_score(0.9),
+                &config,
+            );
+            statuses.push(tracker.status());
+        }
+        assert_eq!(
+            statuses,
+            vec![
+                TrackStatus::Tentative,
+                TrackStatus::Confirmed,
+                TrackStatus::Confirmed
+            ]
+        );
+
+        // A missed frame resets the hit streak but not the status.
+        tracker.predict();
+        tracker.predict();
+        assert_eq!(tracker.hit_streak, 0);
+        assert_eq!(tracker.status(), TrackStatus::Confirmed);
+    }
+
+    #[test]
+    fn test_observations_remain_after_predicts_without_updates() {
+        let bbox = BBox::new(0.0, 0.0, 10.0, 10.0);
+        let mut tracker = KalmanBoxTracker::new(bbox, 1, 3, 0);
+        for _ in 0..10 {
+            tracker.predict();
+            assert_eq!(tracker.get_last_observation(), &bbox);
+            assert_eq!(tracker.get_observation_dt_time_steps_away(), &bbox);
+        }
+
+        let mut state = tracker.save_state();
+        state.observations.clear();
+        let tracker = KalmanBoxTracker::from_state(state, &OCSortConfig::default());
+        assert_eq!(tracker.observation_history().len(), 1);
+    }
+
+    #[test]
+    fn test_larger_process_noise_follows_jumps_more_tightly() {
+        let high_noise_config = OCSortConfig {
+            process_noise: [100.0, 100.0, 100.0, 100.0, 0.01, 0.01, 0.0001],
+            ..Default::default()
+        };
+        let bbox = BBox::new(0.0, 0.0, 10.0, 10.0);
+        let jumped_bbox = BBox::new(20.0, 0.0, 30.0, 10.0);
+
+        let mut estimates = Vec::new();
+        for config in [OCSortConfig::default(), high_noise_config] {
+            let mut tracker = KalmanBoxTracker::with_config(bbox, 1, 0, &config);
+            for (i, bbox) in [bbox, bbox, bbox, jumped_bbox].into_iter().enumerate() {
+                tracker.predict();
+                tracker.update(&Detection::new(bbox, 1).with_score(0.9), &config);
+                if i == 3 {
+                    estimates.push(tracker.get_bbox().x_1);
+                }
+            }
+        }
+
+        assert!((20.0 - estimates[1]).abs() < (20.0 - estimates[0]).abs());
+    }
+
+    #[test]
+    fn test_large_initial_velocity_covariance_converges_faster() {
+        let small_covariance_config = OCSortConfig {
+            initial_covariance: [10.0, 10.0, 10.0, 10.0, 0.01, 0.01, 0.01],
+            ..Default::default()
+        };
+
+        let mut velocity_errors = Vec::new();
+        for config in [OCSortConfig::default(), small_covariance_config] {
+            let mut tracker =
+                KalmanBoxTracker::with_config(BBox::new(0.0, 0.0, 10.0, 10.0), 1, 0, &config);
+            for i in 1..4 {
+                let x = 5.0 * i as f64;
+                tracker.predict();
+                tracker.update(
+                    &Detection::new(BBox::new(x, 0.0, x + 10.0, 10.0), 1).with_score(0.9),
+                    &config,
+                );
+            }
+            velocity_errors.push((tracker.kalman_filter.state()[4] - 5.0).abs());
+        }
+
+        assert!(velocity_errors[0] < velocity_errors[1]);
+    }
+
+    #[test]
+    fn test_update_with_low_score_moves_estimate_less() {
+        let config = OCSortConfig {
+            score_weighted_noise: true,
+            ..Default::default()
+        };
+        let bbox = BBox::new(0.0, 0.0, 10.0, 10.0);
+        let displaced_bbox = BBox::new(5.0, 0.0, 15.0, 10.0);
+
+        let mut low_score_tracker = KalmanBoxTracker::new(bbox, 1, 3, 0);
+        low_score_tracker.predict();
+        low_score_tracker.update(&Detection::new(displaced_bbox, 1).with_score(0.2), &config);
+
+        let mut high_score_tracker = KalmanBoxTracker::new(bbox, 1, 3, 1);
+        high_score_tracker.predict();
+        high_score_tracker.update(&Detection::new(displaced_bbox, 1).with_score(0.9), &config);
+
+        let low_score_shift = low_score_tracker.get_bbox().x_1 - bbox.x_1;
+        let high_score_shift = high_score_tracker.get_bbox().x_1 - bbox.x_1;
+
+        assert!(low_score_shift > 0.0);
+        assert!(low_score_shift < high_score_shift);
+    }
+
+    #[test]
+    fn test_update_with_high_x_uncertainty_moves_estimate_less_in_x() {
+        let bbox = BBox::new(0.0, 0.0, 10.0, 10.0);
+        let mut tracker = KalmanBoxTracker::new(bbox, 1, 3, 0);
+        tracker.predict();
+        tracker.update(
+            &Detection::new(BBox::new(5.0, 5.0, 15.0, 15.0), 1)
+                .with_score(0.9)
+                .with_uncertainty([100.0, 1.0, 1.0, 1.0]),
+            &OCSortConfig::default(),
+        );
+
+        let x_shift = tracker.get_bbox().x_1 - bbox.x_1;
+        let y_shift = tracker.get_bbox().y_1 - bbox.y_1;
+
+        assert!(x_shift > 0.0);
+        assert!(x_shift < y_shift);
+    }
+
+    #[test]
+    fn test_uncertainty_shrinks_with_updates_and_grows_while_coasting() {
+        let config = OCSortConfig::default();
+        let mut tracker = KalmanBoxTracker::new(BBox::new(0.0, 0.0, 10.0, 10.0), 1, 3, 0);
+        let initial_uncertainty = tracker.get_state().uncertainty;
+        assert_eq!(initial_uncertainty, (10.0_f64.sqrt(), 10.0_f64.sqrt()));
+
+        for i in 1..=5 {
+            let x = i as f64;
+            tracker.predict();
+            tracker.update(
+                &Detection::new(BBox::new(x, 0.0, x + 10.0, 10.0), 1).with_score(0.9),
+                &config,
+            );
+        }
+        let tracked_uncertainty = tracker.get_state().uncertainty;
+        assert!(tracked_uncertainty.0 < initial_uncertainty.0);
+        assert!(tracked_uncertainty.1 < initial_uncertainty.1);
+
+        let mut previous_uncertainty = tracked_uncertainty;
+        for _ in 0..3 {
+            tracker.predict();
+            let uncertainty = tracker.get_state().uncertainty;
+            assert!(uncertainty.0 > previous_uncertainty.0);
+            assert!(uncertainty.1 > previous_uncertainty.1);
+            previous_uncertainty = uncertainty;
+        }
+        let covariance = tracker.position_covariance();
+        assert_eq!(covariance[(0, 0)].sqrt(), previous_uncertainty.0);
+    }
+
+    #[test]
+    fn test_recovery_re_updates_along_virtual_trajectory() {
+        let config = OCSortConfig::default();
+        let mut recovered_tracker = KalmanBoxTracker::new(BBox::new(0.0, 0.0, 10.0, 10.0), 1, 3, 0);
+        let mut observed_tracker = KalmanBoxTracker::new(BBox::new(0.0, 0.0, 10.0, 10.0), 1, 3, 1);
+        for i in 1..=3 {
+            for tracker in [&mut recovered_tracker, &mut observed_tracker] {
+                tracker.predict();
+                tracker.update(&detection_at(5.0 * i as f64, 1), &config);
+            }
+        }
+
+        // The recovered tracker misses the object for 3 updates while the observed tracker
+        // sees the boxes of the virtual trajectory.
+        for i in 4..=6 {
+            recovered_tracker.predict();
+            observed_tracker.predict();
+            observed_tracker.update(&detection_at(5.0 * i as f64, 1), &config);
+        }
+        recovered_tracker.predict();
+        observed_tracker.predict();
+        recovered_tracker.update(&detection_at(35.0, 1), &config);
+        observed_tracker.update(&detection_at(35.0, 1), &config);
+
+        let recovered_state = recovered_tracker.kalman_filter.state();
+        let observed_state = observed_tracker.kalman_filter.state();
+        assert!((recovered_state - observed_state).abs().max() < 1e-9);
+        let covariance_difference = recovered_tracker.kalman_filter.covariance()
+            - observed_tracker.kalman_filter.covariance();
+        assert!(covariance_difference.abs().max() < 1e-9);
+        assert_eq!(
+            recovered_tracker.interpolated_gap(),
+            vec![
+                (4, BBox::new(20.0, 0.0, 30.0, 10.0)),
+                (5, BBox::new(25.0, 0.0, 35.0, 10.0)),
+                (6, BBox::new(30.0, 0.0, 40.0, 10.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_velocity_damping_shrinks_coasting_displacement() {
+        let damped_config = OCSortConfig {
+            velocity_damping: 0.5,
+            ..Default::default()
+        };
+        let mut trackers = [
+            KalmanBoxTracker::with_config(
+                detection_at(0.0, 1).bbox,
+                1,
+                0,
+                &OCSortConfig::default(),
+            ),
+            KalmanBoxTracker::with_config(detection_at(0.0, 1).bbox, 1, 1, &damped_config),
+        ];
+        for i in 1..=5 {
+            for tracker in &mut trackers {
+                tracker.predict();
+                tracker.update(&detection_at(5.0 * i as f64, 1), &OCSortConfig::default());
+            }
+        }
+
+        let mut previous_x = trackers.each_ref().map(|tracker| tracker.get_bbox().x_1);
+        let mut previous_damped_displacement = f64::INFINITY;
+        // The first prediction does not know that the object will be missed.
+        for missed_updates in 0..4 {
+            let x = trackers.each_mut().map(|tracker| tracker.predict().x_1);
+            let [undamped_displacement, damped_displacement] =
+                [x[0] - previous_x[0], x[1] - previous_x[1]];
+            if missed_updates == 0 {
+                assert_eq!(damped_displacement, undamped_displacement);
+            } else {
+                assert!(damped_displacement > 0.0);
+                assert!(damped_displacement < previous_damped_displacement);
+                assert!(damped_displacement < undamped_displacement);
+            }
+            previous_damped_displacement = damped_displacement;
+            previous_x = x;
+        }
+    }
+
+    #[test]
+    fn test_velocity_damping_shrinks_coasting_velocity_covariance() {
+        let damped_config = OCSortConfig {
+            velocity_damping: 0.5,
+            ..Default::default()
+        };
+        let mut trackers = [&OCSortConfig::default(), &damped_config]
+            .map(|config| KalmanBoxTracker::with_config(detection_at(0.0, 1).bbox, 1, 0, config));
+        for _ in 0..3 {
+            for tracker in &mut trackers {
+                tracker.predict();
+            }
+        }
+
+        let [undamped, damped] = trackers
+            .each_ref()
+            .map(|tracker| *tracker.kalman_filter.covariance());
+        for i in 4..7 {
+            assert!(damped[(i, i)] < undamped[(i, i)]);
+        }
+    }
+
+    #[test]
+    fn test_fitted_speed_direction_is_more_stable_on_noisy_trajectory() {
+        // The object moves right at a constant speed while the detections jitter vertically.
+        let jitter = [
+            0.0, 1.5, -1.0, 2.0, -1.5, 0.5, -2.0, 1.0, -0.5, 1.5, -1.5, 0.0,
+        ];
+        let detection = |i: usize| {
+            let x = 3.0 * i as f64;
+            Detection::new(BBox::new(x, jitter[i], x + 10.0, jitter[i] + 10.0), 1)
+        };
+        let fit_config = OCSortConfig {
+            fit_speed_direction: true,
+            ..Default::default()
+        };
+        let configs = [OCSortConfig::default(), fit_config];
+        let mut trackers = configs
+            .each_ref()
+            .map(|config| KalmanBoxTracker::with_config(detection(0).bbox, 1, 0, config));
+
+        let mut max_deviations = [0.0_f64; 2];
+        for i in 1..jitter.len() {
+            for ((tracker, config), max_deviation) in
+                trackers.iter_mut().zip(&configs).zip(&mut max_deviations)
+            {
+                tracker.predict();
+                tracker.update(&detection(i), config);
+                // The angle between the speed direction and the true direction (1, 0).
+                let deviation = tracker.speed_direction[1]
+                    .atan2(tracker.speed_direction[0])
+                    .abs();
+                // Both directions use the same two points until the window is full.
+                if i > config.delta_t as usize {
+                    *max_deviation = max_deviation.max(deviation);
+                }
+            }
+        }
+
+        let [two_point_deviation, fitted_deviation] = max_deviations;
+        assert!(fitted_deviation < two_point_deviation);
+        assert!(fitted_deviation < 0.2);
+    }
+
+    #[test]
+    fn test_bbox_stays_in_sync_with_filter_state() {
+        let assert_in_sync = |tracker: &KalmanBoxTracker| {
+            assert_eq!(
+                tracker.get_bbox(),
+                BBox::from_state_vector(*tracker.kalman_filter.state())
+            );
+        };
+        let config = OCSortConfig::default();
+
+        let mut tracker = KalmanBoxTracker::with_config(detection_at(0.0, 1).bbox, 1, 0, &config);
+        for x in [1.0, 2.0] {
+            tracker.predict();
+            assert_in_sync(&tracker);
+            tracker.update(&detection_at(x, 1), &config);
+            assert_in_sync(&tracker);
+        }
+
+        // Found again after two missed updates, which re-updates the filter.
+        tracker.predict();
+        tracker.predict();
+        tracker.predict();
+        tracker.update(&detection_at(5.0, 1), &config);
+        assert!(!tracker.interpolated_gap().is_empty());
+        assert_in_sync(&tracker);
+
+        let mut tracker = KalmanBoxTracker::from_state(tracker.save_state(), &config);
+        assert_in_sync(&tracker);
+
+        let other = KalmanBoxTracker::with_config(detection_at(20.0, 1).bbox, 1, 1, &config);
+        tracker.merge_observations(&other, &config);
+        assert_in_sync(&tracker);
+    }
 }
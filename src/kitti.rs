@@ -0,0 +1,82 @@
+//! Writing of the KITTI tracking result format.
+
+use std::io::{self, Write};
+
+use crate::kalman_box_tracker::Track;
+
+/// The type written for tracks whose class has no name in the class name table.
+const UNKNOWN_TYPE: &str = "DontCare";
+
+/// Writes the tracks of a frame as KITTI tracking result lines
+/// `frame track_id type truncated occluded alpha x1 y1 x2 y2 h w l x y z ry score`,
+/// one per track.
+///
+/// The type is looked up from class_names with the class id of the track, falling back
+/// to DontCare. Only the 2D bbox and the score are known, so the truncation, occlusion,
+/// observation angle and 3D fields are written with the KITTI placeholder values.
+pub fn write_frame(
+    writer: &mut impl Write,
+    frame_idx: usize,
+    tracks: &[Track],
+    class_names: &[&str],
+) -> io::Result<()> {
+    for track in tracks {
+        let object_type = class_names
+            .get(track.class as usize)
+            .copied()
+            .unwrap_or(UNKNOWN_TYPE);
+        let bbox = track.bbox;
+        writeln!(
+            writer,
+            "{frame_idx} {} {object_type} -1 -1 -10 {} {} {} {} -1 -1 -1 -1000 -1000 -1000 -10 {}",
+            track.id, bbox.x_1, bbox.y_1, bbox.x_2, bbox.y_2, track.score
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bbox::BBox;
+
+    #[test]
+    fn test_write_frame_writes_kitti_line_for_car() {
+        let tracks = [
+            Track {
+                id: 3,
+                bbox: BBox::new(296.5, 164.25, 452.75, 289.0),
+                class: 0,
+                age: 4,
+                time_since_update: 0,
+                score: 0.95,
+                uncertainty: (0.0, 0.0),
+                last_update_frame: 0,
+                hit_streak: 0,
+                confirmed: false,
+            },
+            Track {
+                id: 4,
+                bbox: BBox::new(0.0, 0.0, 10.0, 10.0),
+                class: 5,
+                age: 1,
+                time_since_update: 0,
+                score: 0.5,
+                uncertainty: (0.0, 0.0),
+                last_update_frame: 0,
+                hit_streak: 0,
+                confirmed: false,
+            },
+        ];
+        let mut output = Vec::new();
+
+        write_frame(&mut output, 12, &tracks, &["Car", "Pedestrian"]).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "12 3 Car -1 -1 -10 296.5 164.25 452.75 289 -1 -1 -1 -1000 -1000 -1000 -10 0.95\n\
+             12 4 DontCare -1 -1 -10 0 0 10 10 -1 -1 -1 -1000 -1000 -1000 -10 0.5\n"
+        );
+    }
+}
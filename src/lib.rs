@@ -1,22 +1,88 @@
-mod associate;
+//! Rust implementation of the OC-SORT multi-object tracker with Python bindings.
+//!
+//! ```
+//! use oc_sort::{BBox, Detection, OCSort};
+//!
+//! // max_age, iou_threshold, delta_t, score_threshold, min_hit_streak
+//! let mut oc_sort_tracker = OCSort::new(30, 0.3, 3, 0.5, 1);
+//!
+//! for frame in 0..3 {
+//!     let x = frame as f64;
+//!     let detections = [Detection::new(BBox::new(x, 0.0, x + 10.0, 10.0), 0).with_score(0.9)];
+//!     let tracks = oc_sort_tracker.update(&detections);
+//!     assert_eq!(tracks.len(), 1);
+//!     assert_eq!(tracks[0].id, 0);
+//! }
+//! ```
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod associate;
 mod bbox;
+#[cfg(feature = "std")]
+mod bbox3d;
+#[cfg(feature = "std")]
+mod class_registry;
+#[cfg(feature = "std")]
+mod config;
+#[cfg(feature = "std")]
 mod kalman_box_tracker;
+#[cfg(feature = "std")]
+pub mod kitti;
+#[cfg(feature = "std")]
+mod mask;
+#[cfg(feature = "std")]
+pub mod mot;
+#[cfg(feature = "std")]
+mod obbox;
+#[cfg(feature = "std")]
 mod oc_sort_tracker;
+#[cfg(feature = "python")]
 mod python_api;
-pub use bbox::BBox;
-pub use oc_sort_tracker::{Detection, OCSort};
+#[cfg(feature = "std")]
+pub use associate::{
+    AssociationMetric, AssociationStage, CostMatrix, FrameDebug, HungarianMatcher, Matcher,
+    MatchingStrategy, StageDebug,
+};
+pub use bbox::{BBox, iou_batch};
+#[cfg(feature = "std")]
+pub use bbox3d::BBox3D;
+#[cfg(feature = "std")]
+pub use class_registry::ClassRegistry;
+#[cfg(feature = "std")]
+pub use config::{
+    CHI_SQUARED_95_4_DOF, ClassParams, DegenerateBBoxPolicy, IouThresholdSchedule, OCSortConfig,
+};
+#[cfg(feature = "std")]
+pub use kalman_box_tracker::{KalmanBoxTracker, Track, TrackStatus, TrackerState};
+#[cfg(feature = "std")]
+pub use mask::{RleMask, mask_iou};
+#[cfg(feature = "std")]
+pub use obbox::OBBox;
+#[cfg(feature = "std")]
+pub use oc_sort_tracker::{Detection, OCSort, OCSortState};
+#[cfg(feature = "python")]
 use pyo3::{
     Bound, PyResult, pymodule,
     types::{PyModule, PyModuleMethods},
+    wrap_pyfunction,
 };
 
-use crate::python_api::{PyBBox, PyDetection, PyOCSort};
+#[cfg(feature = "python")]
+use crate::python_api::{PyBBox, PyDetection, PyOCSort, PyTrack, py_iou_batch};
 
+#[cfg(feature = "python")]
 #[pymodule]
 fn oc_sort(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<PyBBox>()?;
     m.add_class::<PyDetection>()?;
     m.add_class::<PyOCSort>()?;
+    m.add_class::<PyTrack>()?;
+    m.add_function(wrap_pyfunction!(py_iou_batch, m)?)?;
 
     Ok(())
 }
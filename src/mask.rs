@@ -0,0 +1,131 @@
+/// A binary segmentation mask in the uncompressed run-length encoding of COCO.
+///
+/// The pixels are read in column-major order and counts holds the lengths of the
+/// alternating runs of background and foreground pixels, starting with background.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RleMask {
+    /// The height of the mask in pixels.
+    pub height: u32,
+    /// The width of the mask in pixels.
+    pub width: u32,
+    /// The run lengths, starting with a possibly empty run of background pixels.
+    pub counts: Vec<u32>,
+}
+
+impl RleMask {
+    /// Creates a mask of the given size from the run lengths.
+    pub fn new(height: u32, width: u32, counts: Vec<u32>) -> Self {
+        Self {
+            height,
+            width,
+            counts,
+        }
+    }
+
+    /// Encodes a column-major bitmap of height * width pixels.
+    ///
+    /// Pixels missing from a short bitmap are background and extra pixels are ignored.
+    pub fn from_bitmap(height: u32, width: u32, pixels: &[bool]) -> Self {
+        let size = height as usize * width as usize;
+        let mut counts = Vec::new();
+        let mut value = false;
+        let mut run = 0;
+        for pixel in (0..size).map(|i| pixels.get(i).copied().unwrap_or(false)) {
+            if pixel != value {
+                counts.push(run);
+                value = pixel;
+                run = 0;
+            }
+            run += 1;
+        }
+        counts.push(run);
+
+        Self::new(height, width, counts)
+    }
+
+    /// Returns the number of foreground pixels.
+    pub fn area(&self) -> u64 {
+        self.foreground_runs().map(|(start, end)| end - start).sum()
+    }
+
+    /// Returns the [start, end) pixel ranges of the foreground runs, clipped to the size
+    /// of the mask.
+    fn foreground_runs(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        let size = u64::from(self.height) * u64::from(self.width);
+        let mut position = 0;
+        self.counts
+            .iter()
+            .enumerate()
+            .filter_map(move |(i, &count)| {
+                let start = position.min(size);
+                position += u64::from(count);
+                (i % 2 == 1).then_some((start, position.min(size)))
+            })
+            .filter(|(start, end)| start < end)
+    }
+}
+
+/// Calculates the intersection over union of the foreground pixels of two masks.
+///
+/// Returns 0 if the masks have different sizes or no foreground pixels.
+pub fn mask_iou(mask_1: &RleMask, mask_2: &RleMask) -> f64 {
+    if (mask_1.height, mask_1.width) != (mask_2.height, mask_2.width) {
+        return 0.0;
+    }
+
+    let mut runs_1 = mask_1.foreground_runs().peekable();
+    let mut runs_2 = mask_2.foreground_runs().peekable();
+    let mut intersection = 0;
+    while let (Some(&(start_1, end_1)), Some(&(start_2, end_2))) = (runs_1.peek(), runs_2.peek()) {
+        intersection += end_1.min(end_2).saturating_sub(start_1.max(start_2));
+        if end_1 < end_2 {
+            runs_1.next();
+        } else {
+            runs_2.next();
+        }
+    }
+    let union = mask_1.area() + mask_2.area() - intersection;
+
+    if union == 0 {
+        return 0.0;
+    }
+
+    intersection as f64 / union as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_bitmap_encodes_runs() {
+        let mask = RleMask::from_bitmap(2, 3, &[true, true, false, true, false, false]);
+
+        assert_eq!(mask.counts, vec![0, 2, 1, 1, 2]);
+        assert_eq!(mask.area(), 3);
+    }
+
+    #[test]
+    fn test_mask_iou_counts_overlapping_pixels() {
+        let mask_1 = RleMask::from_bitmap(2, 3, &[true, true, true, true, false, false]);
+        let mask_2 = RleMask::from_bitmap(2, 3, &[false, true, false, true, true, true]);
+
+        assert!((mask_iou(&mask_1, &mask_2) - 2.0 / 6.0).abs() < 1e-9);
+        assert_eq!(mask_iou(&mask_1, &mask_1), 1.0);
+    }
+
+    #[test]
+    fn test_mask_iou_of_mismatching_sizes_or_empty_masks_is_zero() {
+        let mask = RleMask::from_bitmap(2, 2, &[true; 4]);
+
+        assert_eq!(
+            mask_iou(&mask, &RleMask::from_bitmap(4, 1, &[true; 4])),
+            0.0
+        );
+        assert_eq!(
+            mask_iou(&RleMask::new(2, 2, vec![4]), &RleMask::new(2, 2, vec![4])),
+            0.0
+        );
+    }
+}
@@ -0,0 +1,156 @@
+//! Reading and writing of the MOTChallenge (MOT16/17/20) text formats.
+
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+};
+
+use crate::{bbox::BBox, kalman_box_tracker::Track, oc_sort_tracker::Detection};
+
+/// The detections read from a MOTChallenge det.txt file.
+#[derive(Clone, Debug, Default)]
+pub struct MotDetections {
+    /// The detections of each frame by frame index.
+    pub frames: HashMap<usize, Vec<Detection>>,
+    /// The number of non-empty lines skipped because they could not be parsed.
+    pub malformed_lines: usize,
+}
+
+/// Reads the detections of a MOTChallenge det.txt file with lines
+/// `frame,-1,x,y,w,h,conf,...`.
+///
+/// The bboxes are read as top left corner, width and height, negative confidences are
+/// clamped to 0 and all detections get class 0. Malformed lines are skipped and counted
+/// in malformed_lines. Only I/O errors of the reader are returned as errors.
+pub fn read_detections(reader: impl BufRead) -> io::Result<MotDetections> {
+    let mut detections = MotDetections::default();
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_detection(&line) {
+            Some((frame_idx, detection)) => detections
+                .frames
+                .entry(frame_idx)
+                .or_default()
+                .push(detection),
+            None => detections.malformed_lines += 1,
+        }
+    }
+
+    Ok(detections)
+}
+
+/// Parses the frame index and the detection of a det.txt line.
+fn parse_detection(line: &str) -> Option<(usize, Detection)> {
+    let mut fields = line.split(',').map(str::trim);
+    let frame_idx = fields.next()?.parse().ok()?;
+    fields.next()?;
+    let mut values = [0.0; 5];
+    for value in &mut values {
+        *value = fields
+            .next()?
+            .parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite())?;
+    }
+    let [x, y, w, h, score] = values;
+
+    Some((
+        frame_idx,
+        Detection::new(BBox::from_xywh(x, y, w, h), 0).with_score(score.max(0.0)),
+    ))
+}
+
+/// Writes the tracks of a frame as MOTChallenge result lines
+/// `frame,id,x,y,w,h,conf,-1,-1,-1`, one per track.
+///
+/// The bboxes are written as top left corner, width and height and the confidence is the
+/// score of the track. The frame index and track ids are written as given, so pass a
+/// 1-based frame index to follow the MOTChallenge convention.
+pub fn write_frame(writer: &mut impl Write, frame_idx: usize, tracks: &[Track]) -> io::Result<()> {
+    for track in tracks {
+        let (x, y, w, h) = track.bbox.to_xywh();
+        writeln!(
+            writer,
+            "{frame_idx},{},{x},{y},{w},{h},{},-1,-1,-1",
+            track.id, track.score
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_detections_groups_by_frame() {
+        let det_txt = "\
+1,-1,10,20,30,60,0.9,-1,-1,-1
+1,-1,0.5,1.5,1.5,2.5,-0.3,-1,-1,-1
+
+2,-1,12,20,30,60,0.8,-1,-1,-1
+2,-1,not,a,detection
+3,-1,1,2,3
+";
+
+        let detections = read_detections(det_txt.as_bytes()).unwrap();
+
+        assert_eq!(detections.malformed_lines, 2);
+        assert_eq!(detections.frames.len(), 2);
+        assert_eq!(
+            detections.frames[&1],
+            vec![
+                Detection::new(BBox::new(10.0, 20.0, 40.0, 80.0), 0).with_score(0.9),
+                Detection::new(BBox::new(0.5, 1.5, 2.0, 4.0), 0).with_score(0.0),
+            ]
+        );
+        assert_eq!(detections.frames[&2].len(), 1);
+        assert_eq!(
+            detections.frames[&2][0].bbox,
+            BBox::new(12.0, 20.0, 42.0, 80.0)
+        );
+    }
+
+    #[test]
+    fn test_write_frame_writes_mot_lines() {
+        let tracks = [
+            Track {
+                id: 1,
+                bbox: BBox::new(10.0, 20.0, 40.0, 80.0),
+                class: 0,
+                age: 3,
+                time_since_update: 0,
+                score: 0.9,
+                uncertainty: (0.0, 0.0),
+                last_update_frame: 0,
+                hit_streak: 0,
+                confirmed: false,
+            },
+            Track {
+                id: 7,
+                bbox: BBox::new(0.5, 1.5, 2.0, 4.0),
+                class: 0,
+                age: 1,
+                time_since_update: 0,
+                score: 0.25,
+                uncertainty: (0.0, 0.0),
+                last_update_frame: 0,
+                hit_streak: 0,
+                confirmed: false,
+            },
+        ];
+        let mut output = Vec::new();
+
+        write_frame(&mut output, 5, &tracks).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "5,1,10,20,30,60,0.9,-1,-1,-1\n5,7,0.5,1.5,1.5,2.5,0.25,-1,-1,-1\n"
+        );
+    }
+}
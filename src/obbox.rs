@@ -0,0 +1,257 @@
+use nalgebra::SVector;
+
+use crate::bbox::BBox;
+
+/// Struct that represents an oriented (rotated) bounding box.
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OBBox {
+    /// The center x coordinate.
+    pub cx: f64,
+    /// The center y coordinate.
+    pub cy: f64,
+    /// The width of the box before rotation.
+    pub width: f64,
+    /// The height of the box before rotation.
+    pub height: f64,
+    /// The counterclockwise rotation of the box around its center in radians.
+    pub angle: f64,
+}
+
+impl OBBox {
+    /// Creates a new oriented bounding box from the center (cx, cy), width, height and
+    /// rotation angle in radians.
+    ///
+    /// Returns a zero size box at the center if the width or height is negative.
+    pub fn new(cx: f64, cy: f64, width: f64, height: f64, angle: f64) -> Self {
+        if width < 0.0 || height < 0.0 {
+            return OBBox {
+                cx,
+                cy,
+                width: 0.0,
+                height: 0.0,
+                angle,
+            };
+        }
+        OBBox {
+            cx,
+            cy,
+            width,
+            height,
+            angle,
+        }
+    }
+
+    /// Creates an oriented bounding box out of the Kalman Filter state vector
+    /// [cx, cy, area, aspect ratio, angle, vx, vy, v_area].
+    pub fn from_state_vector(state_vector: SVector<f64, 8>) -> Self {
+        if state_vector[2] < 0.0 || state_vector[3] < 0.0 {
+            return OBBox::new(state_vector[0], state_vector[1], 0.0, 0.0, state_vector[4]);
+        }
+        let width = (state_vector[2] * state_vector[3]).sqrt();
+        let height = if width > 0.0 {
+            state_vector[2] / width
+        } else {
+            0.0
+        };
+
+        Self::new(
+            state_vector[0],
+            state_vector[1],
+            width,
+            height,
+            state_vector[4],
+        )
+    }
+
+    /// Creates a Kalman Filter observation vector [cx, cy, area, aspect ratio, angle]
+    /// out of this bounding box.
+    pub fn to_observation_vector(&self) -> SVector<f64, 5> {
+        let area = self.area();
+        let r = self.width / (self.height + f64::EPSILON);
+
+        SVector::<f64, 5>::new(self.cx, self.cy, area, r, self.angle)
+    }
+
+    /// Returns the corners of the box in counterclockwise order.
+    pub fn corners(&self) -> [(f64, f64); 4] {
+        let (sin, cos) = self.angle.sin_cos();
+        let half_width = self.width / 2.0;
+        let half_height = self.height / 2.0;
+
+        [
+            (-half_width, -half_height),
+            (half_width, -half_height),
+            (half_width, half_height),
+            (-half_width, half_height),
+        ]
+        .map(|(x, y)| (self.cx + x * cos - y * sin, self.cy + x * sin + y * cos))
+    }
+
+    /// Returns the smallest axis aligned bounding box that contains this box.
+    pub fn to_bbox(&self) -> BBox {
+        let corners = self.corners();
+        let (x_1, y_1, x_2, y_2) = corners.iter().fold(
+            (
+                f64::INFINITY,
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+                f64::NEG_INFINITY,
+            ),
+            |(x_1, y_1, x_2, y_2), &(x, y)| (x_1.min(x), y_1.min(y), x_2.max(x), y_2.max(y)),
+        );
+
+        BBox::new(x_1, y_1, x_2, y_2)
+    }
+
+    /// Returns the area of the box.
+    pub fn area(&self) -> f64 {
+        self.width * self.height
+    }
+
+    /// Calculates the intersection over union value between this oriented bounding box
+    /// and a given one.
+    ///
+    /// The intersection is computed exactly by clipping one box with the other.
+    pub fn iou(&self, other: &Self) -> f64 {
+        let intersection = polygon_area(&clip_polygon(&self.corners(), &other.corners()));
+        let union = self.area() + other.area() - intersection;
+
+        if union <= 0.0 {
+            return 0.0;
+        }
+
+        (intersection / union).clamp(0.0, 1.0)
+    }
+}
+
+/// Clips the subject polygon with the convex, counterclockwise clip polygon using the
+/// Sutherland-Hodgman algorithm.
+fn clip_polygon(subject: &[(f64, f64)], clip: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut output = subject.to_vec();
+
+    for (i, &edge_start) in clip.iter().enumerate() {
+        let edge_end = clip[(i + 1) % clip.len()];
+        // Positive on the left (inner) side of the edge.
+        let side = |(x, y): (f64, f64)| {
+            (edge_end.0 - edge_start.0) * (y - edge_start.1)
+                - (edge_end.1 - edge_start.1) * (x - edge_start.0)
+        };
+
+        let input = std::mem::take(&mut output);
+        for (j, &current) in input.iter().enumerate() {
+            let previous = input[(j + input.len() - 1) % input.len()];
+            let (current_side, previous_side) = (side(current), side(previous));
+
+            if current_side >= 0.0 {
+                if previous_side < 0.0 {
+                    output.push(intersection(previous, current, previous_side, current_side));
+                }
+                output.push(current);
+            } else if previous_side >= 0.0 {
+                output.push(intersection(previous, current, previous_side, current_side));
+            }
+        }
+
+        if output.is_empty() {
+            break;
+        }
+    }
+
+    output
+}
+
+/// Returns the point where the segment between the points crosses the clip edge,
+/// given the signed sides of the points relative to the edge.
+fn intersection(
+    (x_1, y_1): (f64, f64),
+    (x_2, y_2): (f64, f64),
+    side_1: f64,
+    side_2: f64,
+) -> (f64, f64) {
+    let t = side_1 / (side_1 - side_2);
+
+    (x_1 + t * (x_2 - x_1), y_1 + t * (y_2 - y_1))
+}
+
+/// Returns the area of the polygon with the shoelace formula.
+fn polygon_area(polygon: &[(f64, f64)]) -> f64 {
+    let doubled_area: f64 = polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(&(x_1, y_1), &(x_2, y_2))| x_1 * y_2 - x_2 * y_1)
+        .sum();
+
+    doubled_area.abs() / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_4;
+
+    use super::*;
+
+    #[test]
+    fn test_iou_of_identical_rotated_boxes_is_one() {
+        let obbox = OBBox::new(5.0, 5.0, 4.0, 2.0, 0.3);
+
+        assert!((obbox.iou(&obbox) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_iou_matches_axis_aligned_iou_without_rotation() {
+        let obbox_1 = OBBox::new(5.0, 5.0, 10.0, 10.0, 0.0);
+        let obbox_2 = OBBox::new(10.0, 5.0, 10.0, 10.0, 0.0);
+
+        let expected = obbox_1.to_bbox().iou(&obbox_2.to_bbox());
+        assert!((obbox_1.iou(&obbox_2) - expected).abs() < 1e-9);
+        assert!((expected - 1.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_iou_of_square_and_rotated_square() {
+        // A unit square and the same square rotated by 45 degrees intersect in a
+        // regular octagon with area 2 * (sqrt(2) - 1).
+        let square = OBBox::new(0.0, 0.0, 1.0, 1.0, 0.0);
+        let rotated_square = OBBox::new(0.0, 0.0, 1.0, 1.0, FRAC_PI_4);
+
+        let intersection = 2.0 * (2.0_f64.sqrt() - 1.0);
+        let expected = intersection / (2.0 - intersection);
+        assert!((square.iou(&rotated_square) - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_iou_of_disjoint_boxes_is_zero() {
+        let obbox_1 = OBBox::new(0.0, 0.0, 2.0, 2.0, 0.5);
+        let obbox_2 = OBBox::new(10.0, 0.0, 2.0, 2.0, 1.0);
+
+        assert_eq!(obbox_1.iou(&obbox_2), 0.0);
+    }
+
+    #[test]
+    fn test_state_vector_round_trip() {
+        let obbox = OBBox::new(3.0, 4.0, 6.0, 2.0, 0.7);
+        let observation = obbox.to_observation_vector();
+        let mut state_vector = SVector::<f64, 8>::zeros();
+        state_vector.fixed_rows_mut::<5>(0).copy_from(&observation);
+
+        let round_trip = OBBox::from_state_vector(state_vector);
+        assert!((round_trip.cx - 3.0).abs() < 1e-9);
+        assert!((round_trip.cy - 4.0).abs() < 1e-9);
+        assert!((round_trip.width - 6.0).abs() < 1e-6);
+        assert!((round_trip.height - 2.0).abs() < 1e-6);
+        assert!((round_trip.angle - 0.7).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_to_bbox_encloses_rotated_box() {
+        let obbox = OBBox::new(0.0, 0.0, 2.0, 2.0, FRAC_PI_4);
+        let bbox = obbox.to_bbox();
+        let half_diagonal = 2.0_f64.sqrt();
+
+        assert!((bbox.x_1 + half_diagonal).abs() < 1e-9);
+        assert!((bbox.y_1 + half_diagonal).abs() < 1e-9);
+        assert!((bbox.x_2 - half_diagonal).abs() < 1e-9);
+        assert!((bbox.y_2 - half_diagonal).abs() < 1e-9);
+    }
+}
@@ -1,12 +1,22 @@
+use std::collections::HashMap;
+
 use crate::{
-    associate::{associate_detections_to_trackers, byte_associate, observation_centric_recovery},
+    associate::{
+        AssociationBuffers, FrameDebug, HungarianMatcher, Matcher, run_byte_stage, run_ocr_stage,
+        run_primary_stage,
+    },
     bbox::BBox,
-    kalman_box_tracker::{KalmanBoxTracker, Track},
+    class_registry::ClassRegistry,
+    config::{DegenerateBBoxPolicy, OCSortConfig},
+    kalman_box_tracker::{KalmanBoxTracker, Track, TrackStatus, TrackerState},
+    mask::RleMask,
 };
-use itertools::{Either, Itertools};
 
 /// A detection received from an object detector.
-#[derive(Clone, Copy)]
+///
+/// Create detections with new and the with_* methods, or with a struct expression ending
+/// in `..Default::default()`, so that code keeps compiling when optional fields are added.
+#[derive(Clone, Debug, PartialEq)]
 pub struct Detection {
     /// The bounding box of the detection.
     pub bbox: BBox,
@@ -14,6 +24,76 @@ pub struct Detection {
     pub class: u32,
     /// The confidence score of the detection.
     pub score: f64,
+    /// An id assigned to the object by an external model, e.g. a re-ID model.
+    /// Biases association towards the track that last saw the same id when use_ext_ids
+    /// is enabled in the config.
+    pub ext_id: Option<u64>,
+    /// An appearance feature vector of the object, e.g. from a re-ID model. Adds a cosine
+    /// distance term to the association cost when appearance_weight is set in the config.
+    pub embedding: Option<Vec<f32>>,
+    /// Per-coordinate uncertainty of the box as [x, y, area, aspect ratio] factors, e.g. from
+    /// a probabilistic detector head. Scales the measurement noise of each coordinate in the
+    /// Kalman update, so coordinates with a higher uncertainty are trusted less.
+    pub uncertainty: Option<[f64; 4]>,
+    /// The segmentation mask of the object, e.g. from an instance segmentation model.
+    /// Association uses the mask IoU instead of the bbox similarity for the pairs where
+    /// the track has the mask of its last associated detection as well, and that mask is
+    /// from the previous update or the pair is compared in the OCR stage.
+    pub mask: Option<RleMask>,
+}
+
+impl Detection {
+    /// Creates a detection with a score of 1, e.g. for detectors without scores, and no
+    /// external id, embedding, uncertainty or mask. The optional fields can be set with the
+    /// with_* methods.
+    pub fn new(bbox: BBox, class: u32) -> Self {
+        Self {
+            bbox,
+            class,
+            score: 1.0,
+            ext_id: None,
+            embedding: None,
+            uncertainty: None,
+            mask: None,
+        }
+    }
+
+    /// Returns the detection with the given confidence score.
+    pub fn with_score(mut self, score: f64) -> Self {
+        self.score = score;
+        self
+    }
+
+    /// Returns the detection with the given external id.
+    pub fn with_ext_id(mut self, ext_id: u64) -> Self {
+        self.ext_id = Some(ext_id);
+        self
+    }
+
+    /// Returns the detection with the given appearance feature vector.
+    pub fn with_embedding(mut self, embedding: Vec<f32>) -> Self {
+        self.embedding = Some(embedding);
+        self
+    }
+
+    /// Returns the detection with the given per-coordinate uncertainty.
+    pub fn with_uncertainty(mut self, uncertainty: [f64; 4]) -> Self {
+        self.uncertainty = Some(uncertainty);
+        self
+    }
+
+    /// Returns the detection with the given segmentation mask.
+    pub fn with_mask(mut self, mask: RleMask) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+}
+
+impl Default for Detection {
+    /// Returns a detection of class 0 with a default bbox, like Detection::new.
+    fn default() -> Self {
+        Self::new(BBox::default(), 0)
+    }
 }
 
 impl AsRef<Detection> for Detection {
@@ -22,20 +102,78 @@ impl AsRef<Detection> for Detection {
     }
 }
 
+impl From<(BBox, u32, f64)> for Detection {
+    /// Creates a detection from a (bbox, class, score) tuple without an external id,
+    /// embedding, uncertainty or mask.
+    fn from((bbox, class, score): (BBox, u32, f64)) -> Self {
+        Self::new(bbox, class).with_score(score)
+    }
+}
+
+/// Snapshot of the full state of an OCSort instance.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OCSortState {
+    /// The configuration of the tracker.
+    pub config: OCSortConfig,
+    /// The states of the currently tracked objects.
+    pub trackers: Vec<TrackerState>,
+    /// Ids of the removed trackers not yet taken with take_removed_ids.
+    pub removed_ids: Vec<u32>,
+    /// The id given to the next new tracker.
+    pub next_id: Option<u32>,
+    /// The number of updates processed.
+    pub frame_count: u64,
+}
+
+/// Callback invoked with the id and the Track of each new tracker.
+type CreateCallback = Box<dyn FnMut(u32, &Track) + Send + Sync>;
+/// Callback invoked with the id of each removed tracker.
+type DeleteCallback = Box<dyn FnMut(u32) + Send + Sync>;
+
+/// Index and bounding box buffers reused across updates. They are cleared, not freed,
+/// at the start of each update.
+#[derive(Debug, Default)]
+struct Scratch {
+    detection_indices: Vec<usize>,
+    high_score_indices: Vec<usize>,
+    low_score_indices: Vec<usize>,
+    all_tracker_indices: Vec<usize>,
+    association: AssociationBuffers,
+}
+
 /// The OC-SORT tracker.
+///
+/// The tracker keeps scratch buffers for the per frame index partitions and bounding
+/// boxes to avoid reallocating them on every update. They hold on to the capacity of
+/// the largest frame seen, trading a small amount of memory for less allocator churn
+/// in long running trackers.
 pub struct OCSort {
     /// Vec of object that are currently being tracked.
     trackers: Vec<KalmanBoxTracker>,
-    /// The maximum number of updates a tracker can have without new associations to detections.
-    max_age: u32,
-    /// The minimum IoU score need for an association.
-    iou_threshold: f64,
-    /// The time lag used for speed direction calculations.
-    delta_t: u32,
-    /// Score threshold used to divide detections to high and low sets in BYTE association.
-    score_threshold: f64,
-    /// The minimum number of consecutive association a track needs to be returned.
-    min_hit_streak: u32,
+    /// The configuration of the tracker.
+    config: OCSortConfig,
+    /// Ids of the trackers removed since the last call to take_removed_ids.
+    removed_ids: Vec<u32>,
+    /// The number of detections rejected by the degenerate_bbox_policy since the last call
+    /// to take_rejected_count.
+    rejected_count: usize,
+    /// The id given to the next new tracker. None once every u32 id has been handed out.
+    next_id: Option<u32>,
+    /// The number of updates processed since the creation or the last reset.
+    frame_count: u64,
+    /// Solves the assignments of the Hungarian matching strategy.
+    matcher: Box<dyn Matcher>,
+    /// The names of the class ids.
+    class_registry: Option<ClassRegistry>,
+    /// Called when a new tracker is created.
+    on_create: Option<CreateCallback>,
+    /// Called when a tracker is removed for going over max_age.
+    on_delete: Option<DeleteCallback>,
+    /// The detections collected by update_iter, kept to reuse the allocation across frames.
+    detection_buffer: Vec<Detection>,
+    /// Buffers reused across updates.
+    scratch: Scratch,
 }
 
 impl OCSort {
@@ -54,27 +192,233 @@ impl OCSort {
         score_threshold: f64,
         min_hit_streak: u32,
     ) -> Self {
-        Self {
-            trackers: Vec::new(),
+        Self::from_config(OCSortConfig {
             max_age,
             iou_threshold,
             delta_t,
             score_threshold,
             min_hit_streak,
+            ..Default::default()
+        })
+    }
+
+    /// Creates a new OCSort instance from the given configuration with no initial tracked objects.
+    ///
+    /// ## Panics
+    /// Panics if a parameter of the config is out of range:
+    ///  - cost_multiplier is zero, negative or not finite.
+    ///  - velocity_damping or score_decay is not in [0, 1].
+    ///  - only one of max_age_seconds and fps is set, either is zero, negative or not
+    ///    finite, or their product does not fit in a u32 number of frames.
+    pub fn from_config(config: OCSortConfig) -> Self {
+        config.validate();
+        Self {
+            trackers: Vec::new(),
+            config,
+            removed_ids: Vec::new(),
+            rejected_count: 0,
+            next_id: Some(0),
+            frame_count: 0,
+            matcher: Box::new(HungarianMatcher),
+            class_registry: None,
+            on_create: None,
+            on_delete: None,
+            detection_buffer: Vec::new(),
+            scratch: Scratch::default(),
         }
     }
 
-    /// Returns the currently tracked objects filtered by min_hit_streak.
+    /// Returns the currently tracked objects filtered by min_hit_streak. Returns nothing
+    /// during the first warmup_frames updates.
+    ///
+    /// The tracks are sorted by id, so the order is stable from frame to frame.
     ///
     /// Does not update the state of the tracks.
     pub fn get_trackers(&self) -> Vec<Track> {
+        let mut tracks: Vec<Track> = self
+            .trackers
+            .iter()
+            .filter(|tracker| self.is_active(tracker))
+            .map(|tracker| self.to_track(tracker))
+            .collect();
+        tracks.sort_unstable_by_key(|track| track.id);
+        tracks
+    }
+
+    /// Returns the ids of the tracked objects returned by get_trackers in ascending order.
+    ///
+    /// Does not update the state of the tracks.
+    pub fn active_ids(&self) -> Vec<u32> {
+        let mut ids: Vec<u32> = self
+            .trackers
+            .iter()
+            .filter(|tracker| self.is_active(tracker))
+            .map(|tracker| tracker.get_id())
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Returns the number of tracked objects returned by get_trackers per class id.
+    ///
+    /// Does not update the state of the tracks.
+    pub fn count_by_class(&self) -> HashMap<u32, usize> {
+        let mut counts = HashMap::new();
         self.trackers
             .iter()
-            .filter(|tracker| {
-                (tracker.time_since_update < 1) & (tracker.hit_streak >= self.min_hit_streak)
-            })
-            .map(|tracker| tracker.get_state())
-            .collect()
+            .filter(|tracker| self.is_active(tracker))
+            .for_each(|tracker| *counts.entry(tracker.class).or_insert(0) += 1);
+        counts
+    }
+
+    /// Returns all currently tracked objects with their predicted bounding boxes,
+    /// including tracks that were not associated in the last update.
+    ///
+    /// Unlike get_trackers, the tracks are not filtered by time_since_update or min_hit_streak.
+    /// The tracks are sorted by id like in get_trackers.
+    pub fn get_predicted_tracks(&self) -> Vec<Track> {
+        let mut tracks: Vec<Track> = self
+            .trackers
+            .iter()
+            .map(|tracker| self.to_track(tracker))
+            .collect();
+        tracks.sort_unstable_by_key(|track| track.id);
+        tracks
+    }
+
+    /// Returns the observed (time_step, bbox) pairs of the tracked object with the given id,
+    /// oldest first, or None if no such object is tracked. The time steps count the updates
+    /// since the track was created.
+    ///
+    /// Only the last delta_t + 1 observations of each track are kept unless keep_full_history
+    /// is enabled in the config.
+    pub fn trajectory(&self, id: u32) -> Option<Vec<(u32, BBox)>> {
+        self.trackers
+            .iter()
+            .find(|tracker| tracker.get_id() == id)
+            .map(|tracker| tracker.observation_history())
+    }
+
+    /// Returns the (time_step, bbox) pairs linearly interpolated over the updates the tracked
+    /// object with the given id missed between its last two observations, e.g. to fill the
+    /// gap in a visualization after a re-association. The time steps count the updates since
+    /// the track was created, like in trajectory.
+    ///
+    /// Returns an empty Vec if no such object is tracked or its last two observations are
+    /// consecutive.
+    pub fn interpolated_gap(&self, id: u32) -> Vec<(u32, BBox)> {
+        self.trackers
+            .iter()
+            .find(|tracker| tracker.get_id() == id)
+            .map(|tracker| tracker.interpolated_gap())
+            .unwrap_or_default()
+    }
+
+    /// Restores an OCSort instance from a snapshot created with save_state.
+    ///
+    /// ## Panics
+    /// Panics if a parameter of the config is out of range, e.g. in an edited snapshot. See
+    /// from_config for the checked parameters.
+    pub fn load_state(state: OCSortState) -> Self {
+        state.config.validate();
+        Self {
+            trackers: state
+                .trackers
+                .into_iter()
+                .map(|tracker_state| KalmanBoxTracker::from_state(tracker_state, &state.config))
+                .collect(),
+            config: state.config,
+            removed_ids: state.removed_ids,
+            rejected_count: 0,
+            next_id: state.next_id,
+            frame_count: state.frame_count,
+            matcher: Box::new(HungarianMatcher),
+            class_registry: None,
+            on_create: None,
+            on_delete: None,
+            detection_buffer: Vec::new(),
+            scratch: Scratch::default(),
+        }
+    }
+
+    /// Returns a snapshot of the full state of the tracker that can be used
+    /// to continue tracking later with load_state.
+    pub fn save_state(&self) -> OCSortState {
+        OCSortState {
+            config: self.config.clone(),
+            trackers: self
+                .trackers
+                .iter()
+                .map(|tracker| tracker.save_state())
+                .collect(),
+            removed_ids: self.removed_ids.clone(),
+            next_id: self.next_id,
+            frame_count: self.frame_count,
+        }
+    }
+
+    /// Removes all tracked objects while keeping the configuration.
+    ///
+    /// The ids of the cleared trackers are not reported by take_removed_ids and
+    /// new trackers are given ids starting again from 0. The warmup starts over.
+    pub fn reset(&mut self) {
+        self.trackers.clear();
+        self.removed_ids.clear();
+        self.rejected_count = 0;
+        self.next_id = Some(0);
+        self.frame_count = 0;
+    }
+
+    /// Returns the ids of the trackers that have been removed since the last call
+    /// to this method.
+    ///
+    /// Each id is reported exactly once, after the tracker has been purged.
+    pub fn take_removed_ids(&mut self) -> Vec<u32> {
+        std::mem::take(&mut self.removed_ids)
+    }
+
+    /// Returns the number of detections rejected by the degenerate_bbox_policy of the config
+    /// since the last call to this method.
+    ///
+    /// The count is not saved by save_state.
+    pub fn take_rejected_count(&mut self) -> usize {
+        std::mem::take(&mut self.rejected_count)
+    }
+
+    /// Replaces the Hungarian solver used with the Hungarian matching strategy, e.g. with
+    /// a LAPJV or auction algorithm implementation.
+    ///
+    /// The matcher is not used with the Greedy matching strategy. It is kept by reset and
+    /// is not saved by save_state.
+    pub fn set_matcher(&mut self, matcher: impl Matcher + 'static) {
+        self.matcher = Box::new(matcher);
+    }
+
+    /// Sets the names of the class ids, e.g. to resolve them with Track::class_name.
+    ///
+    /// The registry is kept by reset and is not saved by save_state.
+    pub fn set_class_registry(&mut self, registry: ClassRegistry) {
+        self.class_registry = Some(registry);
+    }
+
+    /// Returns the names of the class ids set with set_class_registry.
+    pub fn class_registry(&self) -> Option<&ClassRegistry> {
+        self.class_registry.as_ref()
+    }
+
+    /// Sets a callback invoked during update with the id and the Track of each new tracker.
+    ///
+    /// The callback is kept by reset and is not saved by save_state.
+    pub fn on_create(&mut self, callback: impl FnMut(u32, &Track) + Send + Sync + 'static) {
+        self.on_create = Some(Box::new(callback));
+    }
+
+    /// Sets a callback invoked with the id of each tracker removed for going over max_age
+    /// or with remove_track. Trackers cleared by reset are not reported.
+    ///
+    /// The callback is kept by reset and is not saved by save_state.
+    pub fn on_delete(&mut self, callback: impl FnMut(u32) + Send + Sync + 'static) {
+        self.on_delete = Some(Box::new(callback));
     }
 
     /// Update the state of the tracked objects and associate them to the detections.
@@ -83,65 +427,289 @@ impl OCSort {
     /// to the score_threshold.
     ///
     /// Uses the OC-SORT algorithm with BYTE association.
+    ///
+    /// ## Panics
+    /// Panics if a new track is needed after every u32 id has been handed out.
+    /// Ids are never reused within an instance until reset is called.
     pub fn update(&mut self, detections: &[Detection]) -> Vec<Track> {
+        self.update_inner(detections, 1.0, None)
+    }
+
+    /// Starts tracking an object at a known position, e.g. from a manual annotation of the
+    /// first frame, and returns the id of the new track.
+    ///
+    /// The track is confirmed and returned by get_trackers immediately, with a score of 1.
+    /// It is predicted and associated like any other track in the following updates.
+    /// Its last_update_frame is the index of the next update.
+    ///
+    /// ## Panics
+    /// Panics if every u32 id has been handed out.
+    pub fn add_track(&mut self, bbox: BBox, class: u32) -> u32 {
+        self.create_tracker(&Detection::new(bbox, class), true)
+    }
+
+    /// Stops tracking the object with the given id, e.g. a track marked as a false positive.
+    /// Returns false if no such object is tracked.
+    ///
+    /// The id is reported by take_removed_ids and on_delete like the ids of expired tracks.
+    /// Ids are never reused within an instance, so a new track never gets the removed id.
+    pub fn remove_track(&mut self, id: u32) -> bool {
+        let Some(index) = self
+            .trackers
+            .iter()
+            .position(|tracker| tracker.get_id() == id)
+        else {
+            return false;
+        };
+        self.trackers.remove(index);
+        self.removed_ids.push(id);
+        if let Some(on_delete) = self.on_delete.as_mut() {
+            on_delete(id);
+        }
+        true
+    }
+
+    /// Merges the object with id remove_id into the object with id keep_id, e.g. when a
+    /// single object was split into two tracks. Returns false if either object is not
+    /// tracked or the ids are equal.
+    ///
+    /// The observations of the removed track are added to the observation history of the
+    /// kept track where it has no observation of its own. The kept track keeps its Kalman
+    /// Filter estimate, speed direction, class and counters. The removed id is reported by
+    /// take_removed_ids and on_delete like with remove_track.
+    pub fn merge_tracks(&mut self, keep_id: u32, remove_id: u32) -> bool {
+        let position = |id: u32| {
+            self.trackers
+                .iter()
+                .position(|tracker| tracker.get_id() == id)
+        };
+        let (Some(keep_index), Some(remove_index)) = (position(keep_id), position(remove_id))
+        else {
+            return false;
+        };
+        if keep_index == remove_index {
+            return false;
+        }
+
+        let removed = self.trackers.remove(remove_index);
+        let keep_index = if remove_index < keep_index {
+            keep_index - 1
+        } else {
+            keep_index
+        };
+        self.trackers[keep_index].merge_observations(&removed, &self.config);
+        self.removed_ids.push(remove_id);
+        if let Some(on_delete) = self.on_delete.as_mut() {
+            on_delete(remove_id);
+        }
+        true
+    }
+
+    /// Runs update over a sequence of frames and returns the tracks of each frame.
+    ///
+    /// ## Panics
+    /// Panics if a new track is needed after every u32 id has been handed out.
+    pub fn run(&mut self, frames: impl IntoIterator<Item = Vec<Detection>>) -> Vec<Vec<Track>> {
+        frames
+            .into_iter()
+            .map(|detections| self.update(&detections))
+            .collect()
+    }
+
+    /// Same as update, but takes the detections from an iterator, e.g. a streaming source.
+    ///
+    /// The detections are collected into a buffer owned by the tracker that is reused
+    /// across frames, so no detections Vec is allocated per frame once the buffer has
+    /// grown to the largest frame.
+    ///
+    /// ## Panics
+    /// Panics if a new track is needed after every u32 id has been handed out.
+    pub fn update_iter(&mut self, detections: impl IntoIterator<Item = Detection>) -> Vec<Track> {
+        let mut detection_buffer = std::mem::take(&mut self.detection_buffer);
+        detection_buffer.clear();
+        detection_buffer.extend(detections);
+        let tracks = self.update_inner(&detection_buffer, 1.0, None);
+        self.detection_buffer = detection_buffer;
+        tracks
+    }
+
+    /// Same as update, but predicts the tracked objects dt time steps ahead instead of one.
+    /// Use for streams with variable frame intervals, e.g. dt = 2.0 after a dropped frame.
+    ///
+    /// Only the motion model uses dt. time_since_update, max_age, delta_t and the time steps
+    /// of the observations still count updates, whatever their dt.
+    ///
+    /// ## Panics
+    /// Panics if dt is not positive and finite, or if a new track is needed after every u32
+    /// id has been handed out.
+    pub fn update_dt(&mut self, detections: &[Detection], dt: f64) -> Vec<Track> {
+        self.update_inner(detections, dt, None)
+    }
+
+    /// Advances every tracked object one time step with its motion model without
+    /// associating detections, e.g. to render at a higher rate than the detector runs.
+    /// Returns the predicted tracks like get_predicted_tracks.
+    ///
+    /// The calls do not count as missed updates: the time_since_update and hit_streak of
+    /// the trackers are kept, so a track associated in the next update stays returned with
+    /// min_hit_streak. Their age grows by one, and the next update predicts one further step
+    /// before associating. No tracker is removed. The frame index used for
+    /// last_update_frame and warmup_frames only counts the updates.
+    pub fn predict_only(&mut self) -> Vec<Track> {
         self.trackers.iter_mut().for_each(|tracker| {
-            tracker.predict();
+            tracker.predict_between_updates(1.0);
         });
 
-        self.trackers
-            .retain(|tracker| tracker.time_since_update <= self.max_age);
+        self.get_predicted_tracks()
+    }
 
-        let (high_score_indices, low_score_indices): (Vec<usize>, Vec<usize>) = detections
-            .iter()
-            .enumerate()
-            .partition_map(|(i, detection)| {
-                if detection.score >= self.score_threshold {
-                    Either::Left(i)
-                } else {
-                    Either::Right(i)
+    /// Same as update, but also returns the index sets and the IoU and cost matrices
+    /// of each association stage for debugging and visualization.
+    ///
+    /// ## Panics
+    /// Panics if a new track is needed after every u32 id has been handed out.
+    pub fn update_with_debug(&mut self, detections: &[Detection]) -> (Vec<Track>, FrameDebug) {
+        let mut debug = FrameDebug::default();
+        let tracks = self.update_inner(detections, 1.0, Some(&mut debug));
+        (tracks, debug)
+    }
+
+    fn update_inner(
+        &mut self,
+        detections: &[Detection],
+        dt: f64,
+        debug: Option<&mut FrameDebug>,
+    ) -> Vec<Track> {
+        // Degenerate bboxes are grown on a copy of the detections, so the indices in the
+        // debug output still refer to the given detections.
+        let grown_detections: Vec<Detection>;
+        let detections = match self.config.degenerate_bbox_policy {
+            DegenerateBBoxPolicy::MinSize(min_size)
+                if detections
+                    .iter()
+                    .any(|detection| detection.bbox.is_degenerate()) =>
+            {
+                grown_detections = detections
+                    .iter()
+                    .map(|detection| Detection {
+                        bbox: detection.bbox.with_min_size(min_size),
+                        ..detection.clone()
+                    })
+                    .collect();
+                &grown_detections
+            }
+            _ => detections,
+        };
+
+        let mut scratch = std::mem::take(&mut self.scratch);
+        self.associate_frame(detections, dt, &mut scratch, debug);
+        self.scratch = scratch;
+        self.frame_count = self.frame_count.saturating_add(1);
+
+        self.get_trackers()
+    }
+
+    fn associate_frame(
+        &mut self,
+        detections: &[Detection],
+        dt: f64,
+        scratch: &mut Scratch,
+        mut debug: Option<&mut FrameDebug>,
+    ) {
+        self.trackers.iter_mut().for_each(|tracker| {
+            tracker.predict_dt(dt);
+        });
+
+        self.remove_expired_trackers();
+
+        let mut rejected_count = 0;
+        scratch.detection_indices.clear();
+        scratch
+            .detection_indices
+            .extend((0..detections.len()).filter(|&i| {
+                let bbox = &detections[i].bbox;
+                if self.config.rejects_degenerate(bbox) {
+                    rejected_count += 1;
+                    return false;
                 }
-            });
+                self.config.accepts_bbox(bbox) && !self.config.in_ignore_region(bbox)
+            }));
+        self.rejected_count += rejected_count;
+        if let Some(nms_iou_threshold) = self.config.nms_iou_threshold {
+            non_maximum_suppression(
+                detections,
+                &mut scratch.detection_indices,
+                nms_iou_threshold,
+                self.config.class_agnostic,
+            );
+        }
+
+        scratch.high_score_indices.clear();
+        scratch.low_score_indices.clear();
+        for &i in &scratch.detection_indices {
+            if detections[i].score >= self.config.score_threshold {
+                scratch.high_score_indices.push(i);
+            } else {
+                scratch.low_score_indices.push(i);
+            }
+        }
+
+        let init_threshold = self
+            .config
+            .init_threshold
+            .unwrap_or(self.config.score_threshold);
 
         if self.trackers.is_empty() {
-            for detection_index in high_score_indices {
-                let detection = detections[detection_index];
-                self.trackers.push(KalmanBoxTracker::new(
-                    detection.bbox,
-                    self.delta_t,
-                    detection.class,
-                ));
+            for &detection_index in &scratch.detection_indices {
+                if detections[detection_index].score >= init_threshold {
+                    self.create_tracker(&detections[detection_index], false);
+                }
             }
-            return self.get_trackers();
+            return;
         }
 
         if detections.is_empty() {
-            return self.get_trackers();
+            return;
         }
 
-        let unmatched_tracker_indices: Vec<usize> = (0..self.trackers.len()).into_iter().collect();
+        // Every tracker takes part in the primary stage. The trackers it leaves unmatched
+        // are passed on to the BYTE stage and the ones still unmatched after that to the
+        // OCR stage, so no tracker index is dropped between the stages.
+        scratch.all_tracker_indices.clear();
+        scratch.all_tracker_indices.extend(0..self.trackers.len());
         let (matched_indices, unmatched_detection_indices, unmatched_tracker_indices) =
-            associate_detections_to_trackers(
-                &detections,
-                &high_score_indices,
+            run_primary_stage(
+                detections,
+                &scratch.high_score_indices,
                 &self.trackers,
-                &unmatched_tracker_indices,
-                self.iou_threshold,
+                &scratch.all_tracker_indices,
+                &self.config,
+                self.matcher.as_ref(),
+                &mut scratch.association,
+                debug.as_deref_mut(),
             );
 
-        let (byte_matched_indices, _, unmatched_tracker_indices) = byte_associate(
-            &detections,
-            &low_score_indices,
-            &self.trackers,
-            &unmatched_tracker_indices,
-            self.iou_threshold,
-        );
+        let (byte_matched_indices, unmatched_low_score_indices, byte_unmatched_tracker_indices) =
+            run_byte_stage(
+                detections,
+                &scratch.low_score_indices,
+                &self.trackers,
+                &unmatched_tracker_indices,
+                &self.config,
+                self.matcher.as_ref(),
+                &mut scratch.association,
+                debug.as_deref_mut(),
+            );
 
-        let (ocr_matched_indices, unmatched_detection_indices, _) = observation_centric_recovery(
-            &detections,
+        let (ocr_matched_indices, unmatched_detection_indices, _) = run_ocr_stage(
+            detections,
             &unmatched_detection_indices,
             &self.trackers,
-            &unmatched_tracker_indices,
-            self.iou_threshold,
+            &byte_unmatched_tracker_indices,
+            &self.config,
+            self.matcher.as_ref(),
+            &mut scratch.association,
+            debug,
         );
 
         for &(detection_index, tracker_index) in matched_indices
@@ -149,95 +717,1355 @@ impl OCSort {
             .chain(byte_matched_indices.iter())
             .chain(ocr_matched_indices.iter())
         {
-            self.trackers[tracker_index].update(detections[detection_index].bbox);
+            self.trackers[tracker_index].update(&detections[detection_index], &self.config);
+            self.trackers[tracker_index].last_update_frame = self.frame_count;
+        }
+
+        // Unmatched high score detections start their tracks before the low score ones.
+        for detection_index in unmatched_detection_indices
+            .into_iter()
+            .chain(unmatched_low_score_indices)
+        {
+            if detections[detection_index].score >= init_threshold {
+                self.create_tracker(&detections[detection_index], false);
+            }
         }
+    }
 
-        for detection_index in unmatched_detection_indices {
-            let detection = detections[detection_index];
-            self.trackers.push(KalmanBoxTracker::new(
-                detection.bbox,
-                detection.class,
-                self.delta_t,
-            ));
+    /// Returns true if the tracker was associated in the last update and has
+    /// a hit streak of at least the min_hit_streak of its class.
+    fn is_active(&self, tracker: &KalmanBoxTracker) -> bool {
+        // frame_count is already incremented past the last update, so the last update is
+        // out of the warmup if its index frame_count - 1 is at least warmup_frames.
+        let warmed_up = self.config.warmup_frames == 0
+            || self.frame_count > u64::from(self.config.warmup_frames);
+        warmed_up
+            & (tracker.time_since_update < 1)
+            & (tracker.hit_streak >= self.config.min_hit_streak_of(tracker.class))
+    }
+
+    /// Returns the Track of the given tracker with the score decayed for each missed update,
+    /// clamped to the frame if frame_size is set.
+    fn to_track(&self, tracker: &KalmanBoxTracker) -> Track {
+        let mut track = tracker.get_state();
+        track.score *= self.config.score_decay.powf(track.time_since_update as f64);
+        if let Some((width, height)) = self.config.frame_size {
+            track.bbox = track.bbox.clamp(width, height);
         }
+        track
+    }
 
-        self.get_trackers()
+    /// Starts tracking the object of the given detection with a new id and returns the id.
+    /// Confirmed trackers start with the hit streak needed to be returned right away.
+    fn create_tracker(&mut self, detection: &Detection, confirmed: bool) -> u32 {
+        let id = self.next_id.expect(
+            "track ids exhausted: every u32 id has been handed out, call reset to start over",
+        );
+        self.next_id = id.checked_add(1);
+        let mut tracker =
+            KalmanBoxTracker::with_config(detection.bbox, detection.class, id, &self.config);
+        tracker.score = detection.score;
+        tracker.ext_id = detection.ext_id;
+        tracker.last_update_frame = self.frame_count;
+        if let Some(embedding) = &detection.embedding {
+            tracker.update_embedding(embedding, self.config.embedding_momentum);
+        }
+        tracker.mask.clone_from(&detection.mask);
+        if confirmed {
+            tracker.hit_streak = tracker
+                .hit_streak
+                .max(self.config.min_hit_streak_of(detection.class));
+        }
+        tracker.update_status(&self.config);
+        if self.on_create.is_some() {
+            let track = self.to_track(&tracker);
+            if let Some(on_create) = self.on_create.as_mut() {
+                on_create(id, &track);
+            }
+        }
+        self.trackers.push(tracker);
+        id
+    }
+
+    /// Removes the trackers that have gone over the max_age of their class, or over
+    /// max_age_tentative if they are still tentative, without an association and records
+    /// their ids. With remove_coasting_in_ignore_regions, also removes the trackers that
+    /// missed the previous update and are predicted inside an ignore region.
+    fn remove_expired_trackers(&mut self) {
+        let config = &self.config;
+        let removed_ids = &mut self.removed_ids;
+        let on_delete = &mut self.on_delete;
+        self.trackers.retain(|tracker| {
+            let max_age = match (tracker.status(), config.max_age_tentative) {
+                (TrackStatus::Tentative, Some(max_age_tentative)) => max_age_tentative,
+                _ => config.max_age_of(tracker.class),
+            };
+            // The trackers have been predicted, so the ones associated in the previous
+            // update have a time_since_update of 1.
+            let ignored = config.remove_coasting_in_ignore_regions
+                && tracker.time_since_update > 1
+                && config.in_ignore_region(&tracker.get_bbox());
+            let expired = tracker.time_since_update > max_age || ignored;
+            if expired {
+                removed_ids.push(tracker.get_id());
+                if let Some(on_delete) = on_delete.as_mut() {
+                    on_delete(tracker.get_id());
+                }
+            }
+            !expired
+        });
+    }
+}
+
+/// Removes the indices of the detections that overlap a higher score detection of the
+/// same class with an IoU above the threshold. The remaining indices are sorted.
+fn non_maximum_suppression(
+    detections: &[Detection],
+    detection_indices: &mut Vec<usize>,
+    iou_threshold: f64,
+    class_agnostic: bool,
+) {
+    detection_indices.sort_by(|&i, &j| {
+        detections[j]
+            .score
+            .total_cmp(&detections[i].score)
+            .then(i.cmp(&j))
+    });
+
+    let mut kept_count = 0;
+    for i in 0..detection_indices.len() {
+        let candidate = &detections[detection_indices[i]];
+        let suppressed = detection_indices[..kept_count].iter().any(|&kept_index| {
+            let kept = &detections[kept_index];
+            (class_agnostic || kept.class == candidate.class)
+                && kept.bbox.iou(&candidate.bbox) > iou_threshold
+        });
+        if !suppressed {
+            detection_indices.swap(kept_count, i);
+            kept_count += 1;
+        }
     }
+
+    detection_indices.truncate(kept_count);
+    detection_indices.sort_unstable();
 }
 
 #[cfg(test)]
 mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
     use super::*;
+    use crate::{associate::AssociationStage, config::ClassParams};
+
+    /// Returns a 10 x 10 detection at x with a score of 0.9.
+    fn detection_at(x: f64, class: u32) -> Detection {
+        Detection::new(BBox::new(x, 0.0, x + 10.0, 10.0), class).with_score(0.9)
+    }
 
     #[test]
-    fn test_update_reassociates_lost_object() {
-        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 1);
-        let detections = vec![Detection {
-            bbox: BBox::new(0.0, 0.0, 1.0, 1.0),
-            class: 1,
-            score: 0.7,
-        }];
+    fn test_added_track_keeps_id_through_updates() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 3);
+        let id = oc_sort_tracker.add_track(BBox::new(0.0, 0.0, 10.0, 10.0), 1);
 
+        assert_eq!(oc_sort_tracker.active_ids(), vec![id]);
+        for i in 1..6 {
+            let x = i as f64;
+            let tracks =
+                oc_sort_tracker.update(&[
+                    Detection::new(BBox::new(x, 0.0, x + 10.0, 10.0), 1).with_score(0.9)
+                ]);
+            assert_eq!(tracks.len(), 1);
+            assert_eq!(tracks[0].id, id);
+        }
+    }
+
+    #[test]
+    fn test_remove_track_drops_live_track() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 1);
+        let detections = [Detection::new(BBox::new(0.0, 0.0, 10.0, 10.0), 1).with_score(0.9)];
         oc_sort_tracker.update(&detections);
-        let detections = vec![Detection {
-            bbox: BBox::new(0.5, 0.0, 1.5, 1.0),
-            class: 1,
-            score: 0.6,
-        }];
-        oc_sort_tracker.update(&detections);
+        let tracks = oc_sort_tracker.update(&detections);
+        assert_eq!(tracks.len(), 1);
+        let id = tracks[0].id;
 
-        oc_sort_tracker.update(&Vec::new());
+        assert!(oc_sort_tracker.remove_track(id));
+        assert!(!oc_sort_tracker.remove_track(id));
+        assert!(oc_sort_tracker.get_predicted_tracks().is_empty());
+        assert_eq!(oc_sort_tracker.take_removed_ids(), vec![id]);
 
-        let detections = vec![Detection {
-            bbox: BBox::new(1.5, 0.0, 2.5, 1.0),
-            class: 1,
-            score: 0.8,
-        }];
         let tracks = oc_sort_tracker.update(&detections);
-
         assert_eq!(tracks.len(), 1);
+        assert_ne!(tracks[0].id, id);
+    }
 
-        let tolerance = 0.1;
-        let track = &tracks[0];
-        assert_eq!(track.class, 1);
-        assert!((track.bbox.x_1 - 1.5).abs() <= tolerance);
-        assert!((track.bbox.x_2 - 2.5).abs() <= tolerance);
-        assert!((track.bbox.y_1 - 0.0).abs() <= tolerance);
-        assert!((track.bbox.y_2 - 1.0).abs() <= tolerance);
+    #[test]
+    fn test_merge_tracks_keeps_one_track_with_kept_id() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 1);
+        let bbox_1 = BBox::new(0.0, 0.0, 10.0, 10.0);
+        let bbox_2 = BBox::new(50.0, 0.0, 60.0, 10.0);
+        let keep_id = oc_sort_tracker.add_track(bbox_1, 1);
+        oc_sort_tracker.update(&[Detection::new(bbox_1, 1)]);
+        let remove_id = oc_sort_tracker.add_track(bbox_2, 1);
+        oc_sort_tracker.update(&[Detection::new(bbox_2, 1)]);
+        let kept_bbox = oc_sort_tracker.trajectory(keep_id).unwrap();
+
+        assert!(!oc_sort_tracker.merge_tracks(keep_id, keep_id));
+        assert!(!oc_sort_tracker.merge_tracks(keep_id, 99));
+        assert!(oc_sort_tracker.merge_tracks(keep_id, remove_id));
+
+        let tracks = oc_sort_tracker.get_predicted_tracks();
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].id, keep_id);
+        assert_eq!(oc_sort_tracker.take_removed_ids(), vec![remove_id]);
+        // The removed track observed the object in the update the kept track missed.
+        let mut expected = kept_bbox;
+        expected.push((2, bbox_2));
+        assert_eq!(oc_sort_tracker.trajectory(keep_id).unwrap(), expected);
     }
 
     #[test]
-    fn test_update_keeps_track_of_objects() {
-        let motorcycle_bboxes = vec![
-            BBox::new(187.0, 324.0, 303.0, 422.0),
-            BBox::new(183.0, 321.0, 302.0, 426.0),
-            BBox::new(180.0, 324.0, 303.0, 429.0),
-            BBox::new(179.0, 324.0, 303.0, 433.0),
-            BBox::new(168.0, 327.0, 305.0, 438.0),
+    fn test_tracks_are_returned_in_ascending_id_order() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 0, 0.5, 1);
+        let bboxes = [
+            BBox::new(60.0, 0.0, 70.0, 10.0),
+            BBox::new(0.0, 0.0, 10.0, 10.0),
+            BBox::new(30.0, 0.0, 40.0, 10.0),
         ];
+        oc_sort_tracker.update(&[Detection::new(bboxes[0], 1), Detection::new(bboxes[1], 1)]);
+        // A new object appears before the tracked ones and one of them is removed.
+        oc_sort_tracker.update(&[Detection::new(bboxes[2], 1), Detection::new(bboxes[1], 1)]);
+        let added_id = oc_sort_tracker.add_track(bboxes[0].translate(0.0, 50.0), 1);
+        oc_sort_tracker.remove_track(0);
 
-        let person_bboxes = vec![
-            BBox::new(213.0, 280.0, 266.0, 402.0),
-            BBox::new(211.0, 278.0, 265.0, 403.0),
-            BBox::new(211.0, 278.0, 269.0, 406.0),
-            BBox::new(210.0, 276.0, 268.0, 405.0),
-            BBox::new(206.0, 277.0, 269.0, 408.0),
+        let frames = [
+            oc_sort_tracker.update(&[
+                Detection::new(bboxes[2], 1),
+                Detection::new(bboxes[0].translate(0.0, 50.0), 1),
+                Detection::new(bboxes[1], 1),
+            ]),
+            oc_sort_tracker.update(&[
+                Detection::new(bboxes[0].translate(0.0, 50.0), 1),
+                Detection::new(bboxes[1], 1),
+                Detection::new(bboxes[2], 1),
+            ]),
         ];
+        for tracks in frames {
+            let ids: Vec<u32> = tracks.iter().map(|track| track.id).collect();
+            assert_eq!(ids, vec![1, 2, added_id]);
+        }
+        assert_eq!(oc_sort_tracker.active_ids(), vec![1, 2, added_id]);
+    }
 
-        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 1);
+    #[test]
+    fn test_update_associates_after_predict_only_steps() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 1, 0.5, 1);
+        let bbox_at = |step: u32| {
+            let x = 4.0 * step as f64;
+            BBox::new(x, 0.0, x + 10.0, 10.0)
+        };
+        for step in 0..5 {
+            oc_sort_tracker.update(&[Detection::new(bbox_at(step), 1)]);
+        }
+        let id = oc_sort_tracker.active_ids()[0];
 
-        for i in 0..motorcycle_bboxes.len() {
-            let detections = vec![
-                Detection {
-                    bbox: motorcycle_bboxes[i],
-                    class: 3,
-                    score: 0.9,
-                },
-                Detection {
-                    bbox: person_bboxes[i],
-                    class: 0,
-                    score: 0.8,
-                },
+        let predicted = oc_sort_tracker.predict_only();
+        assert_eq!(predicted.len(), 1);
+        assert_eq!(predicted[0].time_since_update, 0);
+        let predicted = oc_sort_tracker.predict_only();
+        assert_eq!(predicted[0].time_since_update, 0);
+        // The motion model keeps moving the object while no detections are fed.
+        assert!(predicted[0].bbox.x_1 > bbox_at(5).x_1);
+
+        let tracks = oc_sort_tracker.update(&[Detection::new(bbox_at(7), 1)]);
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].id, id);
+        assert_eq!(oc_sort_tracker.get_predicted_tracks().len(), 1);
+    }
+
+    #[test]
+    fn test_predict_only_keeps_hit_streak_of_associated_tracks() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 3);
+        let bbox_at = |step: u32| {
+            let x = 2.0 * step as f64;
+            BBox::new(x, 0.0, x + 10.0, 10.0)
+        };
+
+        // The detector runs at every other step and predict_only fills the steps between.
+        for step in (0..20).step_by(2) {
+            let tracks = oc_sort_tracker.update(&[Detection::new(bbox_at(step), 1)]);
+            let predicted = oc_sort_tracker.predict_only();
+            if step >= 4 {
+                assert_eq!(tracks.len(), 1, "track dropped at step {step}");
+                assert_eq!(tracks[0].hit_streak, step / 2 + 1);
+                assert!((predicted[0].bbox.x_1 - bbox_at(step + 1).x_1).abs() < 1.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_max_age_seconds_at_fps_behaves_like_max_age_in_frames() {
+        let mut in_frames = OCSort::from_config(OCSortConfig {
+            max_age: 30,
+            ..Default::default()
+        });
+        let mut in_seconds = OCSort::from_config(OCSortConfig {
+            max_age: 5,
+            max_age_seconds: Some(1.0),
+            fps: Some(30.0),
+            ..Default::default()
+        });
+        let detections = [Detection::new(BBox::new(0.0, 0.0, 10.0, 10.0), 1)];
+
+        for frame in 0..40 {
+            let detections: &[Detection] = if frame < 3 { &detections } else { &[] };
+            in_frames.update(detections);
+            in_seconds.update(detections);
+            assert_eq!(
+                in_frames.get_predicted_tracks().len(),
+                in_seconds.get_predicted_tracks().len()
+            );
+        }
+        assert!(in_seconds.get_predicted_tracks().is_empty());
+        assert_eq!(in_seconds.take_removed_ids(), vec![0]);
+    }
+
+    #[test]
+    fn test_track_is_confirmed_on_frame_hit_streak_reaches_min_hit_streak() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 3);
+        let detections = [Detection::new(BBox::new(0.0, 0.0, 10.0, 10.0), 1)];
+
+        let mut confirmed = Vec::new();
+        for _ in 0..5 {
+            oc_sort_tracker.update(&detections);
+            let track = &oc_sort_tracker.get_predicted_tracks()[0];
+            confirmed.push((track.hit_streak, track.confirmed));
+        }
+        assert_eq!(
+            confirmed,
+            vec![(1, false), (2, false), (3, true), (4, true), (5, true)]
+        );
+
+        // A missed update breaks the streak but the track stays confirmed.
+        oc_sort_tracker.update(&[]);
+        oc_sort_tracker.update(&[]);
+        let track = &oc_sort_tracker.get_predicted_tracks()[0];
+        assert_eq!((track.hit_streak, track.confirmed), (0, true));
+    }
+
+    #[test]
+    fn test_run_keeps_track_count_stable() {
+        let frames = (0..10).map(|i| {
+            (0..3)
+                .map(|object| {
+                    let x = object as f64 * 30.0 + i as f64;
+                    Detection::new(BBox::new(x, 0.0, x + 10.0, 10.0), 1).with_score(0.9)
+                })
+                .collect()
+        });
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 3);
+
+        let tracks_per_frame = oc_sort_tracker.run(frames);
+
+        assert_eq!(tracks_per_frame.len(), 10);
+        for tracks in &tracks_per_frame[3..] {
+            let mut ids: Vec<u32> = tracks.iter().map(|track| track.id).collect();
+            ids.sort_unstable();
+            assert_eq!(ids, vec![0, 1, 2]);
+        }
+    }
+
+    #[test]
+    fn test_warmup_frames_suppress_output() {
+        let mut oc_sort_tracker = OCSort::from_config(OCSortConfig {
+            min_hit_streak: 0,
+            warmup_frames: 3,
+            ..Default::default()
+        });
+
+        for i in 0..5 {
+            let x = i as f64;
+            let detections = [Detection::new(BBox::new(x, 0.0, x + 10.0, 10.0), 1).with_score(0.9)];
+            let tracks = oc_sort_tracker.update(&detections);
+            if i < 3 {
+                assert!(tracks.is_empty());
+                assert!(oc_sort_tracker.active_ids().is_empty());
+            } else {
+                assert_eq!(tracks.len(), 1);
+                assert_eq!(tracks[0].id, 0);
+            }
+        }
+
+        oc_sort_tracker.reset();
+        assert!(oc_sort_tracker.get_trackers().is_empty());
+    }
+
+    #[test]
+    fn test_last_update_frame_records_matched_update() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 0);
+        let detections = [Detection::new(BBox::new(0.0, 0.0, 10.0, 10.0), 1).with_score(0.9)];
+
+        for frame in 0..3 {
+            let tracks = oc_sort_tracker.update(&detections);
+            assert_eq!(tracks[0].last_update_frame, frame);
+        }
+        oc_sort_tracker.update(&[]);
+        oc_sort_tracker.update(&[]);
+
+        let tracks = oc_sort_tracker.get_predicted_tracks();
+        assert_eq!(tracks[0].time_since_update, 2);
+        assert_eq!(tracks[0].last_update_frame, 2);
+
+        let tracks = oc_sort_tracker.update(&detections);
+        assert_eq!(tracks[0].last_update_frame, 5);
+    }
+
+    #[test]
+    fn test_interpolated_gap_fills_missed_updates() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 0);
+
+        oc_sort_tracker.update(&[detection_at(0.0, 1)]);
+        oc_sort_tracker.update(&[detection_at(1.0, 1)]);
+        assert!(oc_sort_tracker.interpolated_gap(0).is_empty());
+        for _ in 0..3 {
+            oc_sort_tracker.update(&[]);
+        }
+        let tracks = oc_sort_tracker.update(&[detection_at(5.0, 1)]);
+        assert_eq!(tracks[0].id, 0);
+
+        let gap = oc_sort_tracker.interpolated_gap(0);
+        assert_eq!(
+            gap.iter()
+                .map(|&(time_step, _)| time_step)
+                .collect::<Vec<_>>(),
+            vec![2, 3, 4]
+        );
+        for (&(_, bbox), x) in gap.iter().zip([2.0, 3.0, 4.0]) {
+            let expected = BBox::new(x, 0.0, x + 10.0, 10.0);
+            assert!((bbox.x_1 - expected.x_1).abs() < 1e-6);
+            assert!((bbox.y_1 - expected.y_1).abs() < 1e-6);
+            assert!((bbox.x_2 - expected.x_2).abs() < 1e-6);
+            assert!((bbox.y_2 - expected.y_2).abs() < 1e-6);
+        }
+        assert!(oc_sort_tracker.interpolated_gap(1).is_empty());
+    }
+
+    #[test]
+    fn test_count_by_class_counts_confirmed_tracks() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 2);
+        let detections = [
+            detection_at(0.0, 1),
+            detection_at(20.0, 1),
+            detection_at(40.0, 2),
+            detection_at(60.0, 3),
+        ];
+
+        oc_sort_tracker.update(&detections);
+        oc_sort_tracker.update(&detections);
+        // A track of class 3 that has not yet reached min_hit_streak is not counted.
+        oc_sort_tracker.update(&[
+            detection_at(0.0, 1),
+            detection_at(20.0, 1),
+            detection_at(40.0, 2),
+            detection_at(100.0, 3),
+        ]);
+
+        let counts = oc_sort_tracker.count_by_class();
+        assert_eq!(counts, HashMap::from([(1, 2), (2, 1)]));
+        assert_eq!(
+            counts.values().sum::<usize>(),
+            oc_sort_tracker.get_trackers().len()
+        );
+    }
+
+    #[test]
+    fn test_detection_converts_from_tuple() {
+        let bbox = BBox::new(0.0, 0.0, 10.0, 10.0);
+
+        assert_eq!(
+            Detection::from((bbox, 2, 0.5)),
+            Detection::new(bbox, 2).with_score(0.5)
+        );
+        let detection: Detection = ([10.0, 0.0, 0.0, 10.0].into(), 1, 0.9).into();
+        assert_eq!(detection.bbox, BBox::default());
+    }
+
+    #[test]
+    fn test_detection_builder_sets_fields() {
+        let bbox = BBox::new(0.0, 0.0, 10.0, 10.0);
+
+        let detection = Detection::new(bbox, 2);
+        assert_eq!(detection.score, 1.0);
+        assert_eq!(detection, Detection::from((bbox, 2, 1.0)));
+
+        let detection = Detection::new(bbox, 2)
+            .with_score(0.4)
+            .with_ext_id(7)
+            .with_embedding(vec![1.0, 0.0])
+            .with_uncertainty([2.0, 1.0, 1.0, 1.0]);
+        assert_eq!(
+            detection,
+            Detection::new(bbox, 2)
+                .with_score(0.4)
+                .with_ext_id(7)
+                .with_embedding(vec![1.0, 0.0])
+                .with_uncertainty([2.0, 1.0, 1.0, 1.0])
+        );
+    }
+
+    #[test]
+    fn test_default_score_counts_as_high_score_detection() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 0);
+
+        // Only high score detections create new tracks.
+        let tracks = oc_sort_tracker.update(&[
+            Detection::new(BBox::new(0.0, 0.0, 10.0, 10.0), 1),
+            Detection::new(BBox::new(50.0, 0.0, 60.0, 10.0), 1).with_score(0.2),
+        ]);
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].bbox, BBox::new(0.0, 0.0, 10.0, 10.0));
+        assert_eq!(tracks[0].score, 1.0);
+    }
+
+    #[test]
+    fn test_detections_below_init_threshold_associate_but_never_initiate() {
+        let mut oc_sort_tracker = OCSort::from_config(OCSortConfig {
+            score_threshold: 0.5,
+            init_threshold: Some(0.8),
+            min_hit_streak: 0,
+            ..Default::default()
+        });
+        let bbox = BBox::new(0.0, 0.0, 10.0, 10.0);
+
+        let tracks = oc_sort_tracker.update(&[Detection::new(bbox, 1).with_score(0.6)]);
+        assert!(tracks.is_empty());
+        assert!(oc_sort_tracker.get_predicted_tracks().is_empty());
+
+        oc_sort_tracker.update(&[Detection::new(bbox, 1).with_score(0.9)]);
+        let tracks = oc_sort_tracker.update(&[
+            Detection::new(bbox, 1).with_score(0.6),
+            Detection::new(BBox::new(50.0, 0.0, 60.0, 10.0), 1).with_score(0.6),
+        ]);
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].id, 0);
+        assert_eq!(tracks[0].score, 0.6);
+        assert_eq!(oc_sort_tracker.get_predicted_tracks().len(), 1);
+    }
+
+    #[test]
+    fn test_low_init_threshold_lets_low_score_detections_initiate() {
+        let mut oc_sort_tracker = OCSort::from_config(OCSortConfig {
+            score_threshold: 0.5,
+            init_threshold: Some(0.2),
+            min_hit_streak: 0,
+            ..Default::default()
+        });
+
+        let tracks = oc_sort_tracker.update(&[
+            Detection::new(BBox::new(0.0, 0.0, 10.0, 10.0), 1).with_score(0.3),
+            Detection::new(BBox::new(50.0, 0.0, 60.0, 10.0), 1).with_score(0.1),
+        ]);
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].bbox, BBox::new(0.0, 0.0, 10.0, 10.0));
+
+        let tracks = oc_sort_tracker.update(&[
+            Detection::new(BBox::new(0.0, 0.0, 10.0, 10.0), 1).with_score(0.3),
+            Detection::new(BBox::new(50.0, 0.0, 60.0, 10.0), 1).with_score(0.9),
+            Detection::new(BBox::new(100.0, 0.0, 110.0, 10.0), 1).with_score(0.3),
+        ]);
+        let mut ids: Vec<u32> = tracks.iter().map(|track| track.id).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_track_class_name_resolves_through_registry() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 0);
+        assert!(oc_sort_tracker.class_registry().is_none());
+        oc_sort_tracker.set_class_registry(ClassRegistry::from_iter([(1, "car")]));
+
+        let tracks = oc_sort_tracker.update(&[
+            Detection::new(BBox::new(0.0, 0.0, 10.0, 10.0), 1),
+            Detection::new(BBox::new(50.0, 0.0, 60.0, 10.0), 2),
+        ]);
+        let registry = oc_sort_tracker.class_registry().unwrap();
+
+        assert_eq!(tracks[0].class_name(registry), Some("car"));
+        assert_eq!(tracks[1].class_name(registry), None);
+    }
+
+    #[test]
+    fn test_update_iter_matches_update() {
+        let frames: Vec<Vec<Detection>> = (0..5)
+            .map(|i| {
+                let x = i as f64;
+                vec![
+                    Detection::new(BBox::new(x, 0.0, x + 10.0, 10.0), 1).with_score(0.9),
+                    Detection::new(BBox::new(50.0 - x, 0.0, 60.0 - x, 10.0), 2).with_score(0.4),
+                ]
+            })
+            .collect();
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.3, 1);
+        let mut iter_oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.3, 1);
+
+        for frame in &frames {
+            let tracks = oc_sort_tracker.update(frame);
+            let iter_tracks = iter_oc_sort_tracker.update_iter(frame.iter().cloned());
+
+            assert_eq!(tracks.len(), iter_tracks.len());
+            for (track, iter_track) in tracks.iter().zip(&iter_tracks) {
+                assert_eq!(track.id, iter_track.id);
+                assert_eq!(track.bbox, iter_track.bbox);
+            }
+        }
+    }
+
+    #[test]
+    fn test_detection_below_min_area_never_creates_track() {
+        let mut oc_sort_tracker = OCSort::from_config(OCSortConfig {
+            min_hit_streak: 0,
+            min_area: Some(4.0),
+            ..Default::default()
+        });
+        let detections = [Detection::new(BBox::new(10.0, 10.0, 11.0, 11.0), 1).with_score(0.9)];
+
+        for _ in 0..3 {
+            assert!(oc_sort_tracker.update(&detections).is_empty());
+        }
+        assert!(oc_sort_tracker.get_predicted_tracks().is_empty());
+    }
+
+    #[test]
+    fn test_detection_in_ignore_region_never_creates_track() {
+        let mut oc_sort_tracker = OCSort::from_config(OCSortConfig {
+            min_hit_streak: 0,
+            ignore_regions: vec![BBox::new(0.0, 0.0, 20.0, 100.0)],
+            ignore_region_overlap: Some(0.5),
+            ..Default::default()
+        });
+        let detections = [
+            // Center inside the region.
+            Detection::new(BBox::new(5.0, 10.0, 15.0, 20.0), 1),
+            // Center outside, but 60% of the area inside the region.
+            Detection::new(BBox::new(14.0, 30.0, 24.0, 40.0), 1),
+            // Only 20% of the area inside the region.
+            Detection::new(BBox::new(18.0, 60.0, 28.0, 70.0), 1),
+        ];
+
+        for _ in 0..3 {
+            let tracks = oc_sort_tracker.update(&detections);
+            assert_eq!(tracks.len(), 1);
+            assert_eq!(tracks[0].bbox, detections[2].bbox);
+        }
+        assert_eq!(oc_sort_tracker.get_predicted_tracks().len(), 1);
+    }
+
+    #[test]
+    fn test_coasting_track_in_ignore_region_is_removed() {
+        let config = OCSortConfig {
+            min_hit_streak: 0,
+            ignore_regions: vec![BBox::new(0.0, 0.0, 20.0, 100.0)],
+            remove_coasting_in_ignore_regions: true,
+            ..Default::default()
+        };
+        let mut oc_sort_tracker = OCSort::from_config(config);
+        // The object moves left towards the ignore region at the frame edge.
+        for step in 0..4 {
+            let x = 40.0 - 5.0 * step as f64;
+            oc_sort_tracker.update(&[Detection::new(BBox::new(x, 10.0, x + 10.0, 20.0), 1)]);
+        }
+        let id = oc_sort_tracker.active_ids()[0];
+
+        for _ in 0..4 {
+            oc_sort_tracker.update(&[]);
+        }
+        assert!(oc_sort_tracker.get_predicted_tracks().is_empty());
+        assert_eq!(oc_sort_tracker.take_removed_ids(), vec![id]);
+    }
+
+    #[test]
+    fn test_degenerate_detections_put_no_nan_in_tracks() {
+        let detections = [
+            Detection::new(BBox::new(10.0, 10.0, 10.0, 30.0), 1),
+            Detection::new(BBox::new(50.0, 10.0, 70.0, 10.0), 1),
+            Detection::new(
+                BBox {
+                    x_1: f64::NAN,
+                    ..BBox::new(90.0, 10.0, 100.0, 20.0)
+                },
+                1,
+            ),
+            Detection::new(BBox::new(130.0, 10.0, 140.0, 20.0), 1),
+        ];
+        let has_nan = |track: &Track| {
+            let bbox = track.bbox;
+            [bbox.x_1, bbox.y_1, bbox.x_2, bbox.y_2, track.score]
+                .iter()
+                .any(|value| value.is_nan())
+        };
+
+        for (policy, expected_tracks, expected_rejected) in [
+            (DegenerateBBoxPolicy::Reject, 1, 3),
+            (DegenerateBBoxPolicy::MinSize(1.0), 3, 1),
+        ] {
+            let mut oc_sort_tracker = OCSort::from_config(OCSortConfig {
+                min_hit_streak: 0,
+                degenerate_bbox_policy: policy,
+                ..Default::default()
+            });
+            for frame in 0..3 {
+                let tracks = oc_sort_tracker.update(&detections);
+                assert_eq!(tracks.len(), expected_tracks, "{policy:?}");
+                assert!(!tracks.iter().any(has_nan), "{policy:?}");
+                assert_eq!(
+                    oc_sort_tracker.take_rejected_count(),
+                    expected_rejected,
+                    "{policy:?} frame {frame}"
+                );
+            }
+            assert!(
+                !oc_sort_tracker.get_predicted_tracks().iter().any(has_nan),
+                "{policy:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_nms_collapses_duplicate_detections_to_single_track() {
+        let detections = [
+            Detection::new(BBox::new(10.0, 10.0, 20.0, 20.0), 1).with_score(0.9),
+            Detection::new(BBox::new(10.5, 10.0, 20.5, 20.0), 1).with_score(0.8),
+            Detection::new(BBox::new(10.0, 10.0, 20.0, 20.0), 2).with_score(0.8),
+        ];
+        let config = OCSortConfig {
+            min_hit_streak: 0,
+            ..Default::default()
+        };
+        let mut oc_sort_tracker = OCSort::from_config(config.clone());
+        let mut nms_oc_sort_tracker = OCSort::from_config(OCSortConfig {
+            nms_iou_threshold: Some(0.5),
+            ..config
+        });
+
+        assert_eq!(oc_sort_tracker.update(&detections).len(), 3);
+        let tracks = nms_oc_sort_tracker.update(&detections);
+        assert_eq!(tracks.len(), 2);
+        assert_eq!(tracks[0].bbox, detections[0].bbox);
+        assert_eq!(tracks[1].class, 2);
+    }
+
+    #[test]
+    fn test_update_reassociates_lost_object() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 1);
+        let detections = vec![Detection::new(BBox::new(0.0, 0.0, 1.0, 1.0), 1).with_score(0.7)];
+
+        oc_sort_tracker.update(&detections);
+        let detections = vec![Detection::new(BBox::new(0.5, 0.0, 1.5, 1.0), 1).with_score(0.6)];
+        oc_sort_tracker.update(&detections);
+
+        oc_sort_tracker.update(&Vec::new());
+
+        let detections = vec![Detection::new(BBox::new(1.5, 0.0, 2.5, 1.0), 1).with_score(0.8)];
+        let tracks = oc_sort_tracker.update(&detections);
+
+        assert_eq!(tracks.len(), 1);
+
+        let tolerance = 0.1;
+        let track = &tracks[0];
+        assert_eq!(track.class, 1);
+        assert!((track.bbox.x_1 - 1.5).abs() <= tolerance);
+        assert!((track.bbox.x_2 - 2.5).abs() <= tolerance);
+        assert!((track.bbox.y_1 - 0.0).abs() <= tolerance);
+        assert!((track.bbox.y_2 - 1.0).abs() <= tolerance);
+    }
+
+    #[test]
+    fn test_get_predicted_tracks_includes_coasting_track() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 1);
+        let detections = vec![Detection::new(BBox::new(0.0, 0.0, 1.0, 1.0), 1).with_score(0.7)];
+        oc_sort_tracker.update(&detections);
+
+        let tracks = oc_sort_tracker.update(&Vec::new());
+        assert!(tracks.is_empty());
+
+        let predicted_tracks = oc_sort_tracker.get_predicted_tracks();
+        assert_eq!(predicted_tracks.len(), 1);
+        assert_eq!(predicted_tracks[0].id, 0);
+        assert_eq!(predicted_tracks[0].time_since_update, 1);
+    }
+
+    #[test]
+    fn test_track_score_follows_matched_detection() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 1);
+        let detections = vec![Detection::new(BBox::new(0.0, 0.0, 1.0, 1.0), 1).with_score(0.7)];
+        let tracks = oc_sort_tracker.update(&detections);
+        assert_eq!(tracks[0].score, 0.7);
+
+        let detections = vec![Detection::new(BBox::new(0.1, 0.0, 1.1, 1.0), 1).with_score(0.9)];
+        let tracks = oc_sort_tracker.update(&detections);
+        assert_eq!(tracks[0].score, 0.9);
+    }
+
+    #[test]
+    fn test_coasting_track_score_decays() {
+        let mut oc_sort_tracker = OCSort::from_config(OCSortConfig {
+            score_decay: 0.5,
+            min_hit_streak: 1,
+            ..Default::default()
+        });
+        let detections = vec![Detection::new(BBox::new(0.0, 0.0, 1.0, 1.0), 1).with_score(0.8)];
+        oc_sort_tracker.update(&detections);
+
+        oc_sort_tracker.update(&Vec::new());
+        let tracks = oc_sort_tracker.get_predicted_tracks();
+        assert!((tracks[0].score - 0.4).abs() < 1e-9);
+
+        oc_sort_tracker.update(&Vec::new());
+        let tracks = oc_sort_tracker.get_predicted_tracks();
+        assert!((tracks[0].score - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_class_agnostic_track_survives_class_flip() {
+        let mut oc_sort_tracker = OCSort::from_config(OCSortConfig {
+            class_agnostic: true,
+            min_hit_streak: 1,
+            ..Default::default()
+        });
+        let detections = vec![Detection::new(BBox::new(0.0, 0.0, 10.0, 10.0), 1).with_score(0.9)];
+        oc_sort_tracker.update(&detections);
+
+        let detections = vec![Detection::new(BBox::new(1.0, 0.0, 11.0, 10.0), 2).with_score(0.9)];
+        let tracks = oc_sort_tracker.update(&detections);
+
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].id, 0);
+        assert_eq!(tracks[0].class, 2);
+    }
+
+    #[test]
+    fn test_active_ids_match_get_trackers() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 2);
+        let detections = vec![
+            Detection::new(BBox::new(0.0, 0.0, 10.0, 10.0), 1).with_score(0.9),
+            Detection::new(BBox::new(20.0, 0.0, 30.0, 10.0), 1).with_score(0.9),
+        ];
+        oc_sort_tracker.update(&detections);
+        oc_sort_tracker.update(&detections);
+        // Only the first object is seen in the last frame.
+        oc_sort_tracker.update(&detections[..1]);
+
+        let ids: Vec<u32> = oc_sort_tracker
+            .get_trackers()
+            .iter()
+            .map(|track| track.id)
+            .collect();
+        assert_eq!(oc_sort_tracker.active_ids(), ids);
+        assert_eq!(ids, vec![0]);
+    }
+
+    #[test]
+    fn test_class_params_override_max_age() {
+        let mut oc_sort_tracker = OCSort::from_config(OCSortConfig {
+            max_age: 1,
+            class_params: HashMap::from([(
+                0,
+                ClassParams {
+                    max_age: 5,
+                    min_hit_streak: 3,
+                },
+            )]),
+            ..Default::default()
+        });
+        let detections = vec![
+            Detection::new(BBox::new(0.0, 0.0, 10.0, 10.0), 0).with_score(0.9),
+            Detection::new(BBox::new(20.0, 0.0, 30.0, 10.0), 1).with_score(0.9),
+        ];
+        oc_sort_tracker.update(&detections);
+        for _ in 0..3 {
+            oc_sort_tracker.update(&Vec::new());
+        }
+
+        let classes: Vec<u32> = oc_sort_tracker
+            .get_predicted_tracks()
+            .iter()
+            .map(|track| track.class)
+            .collect();
+        assert_eq!(classes, vec![0]);
+        assert_eq!(oc_sort_tracker.take_removed_ids(), vec![1]);
+    }
+
+    #[test]
+    fn test_trajectory_returns_last_observations() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 1);
+        let bboxes: Vec<BBox> = (0..5)
+            .map(|i| BBox::new(i as f64, 0.0, i as f64 + 10.0, 10.0))
+            .collect();
+        for &bbox in &bboxes {
+            oc_sort_tracker.update(&[Detection::new(bbox, 1).with_score(0.9)]);
+        }
+
+        let trajectory = oc_sort_tracker.trajectory(0).unwrap();
+        assert_eq!(trajectory.len(), 4);
+        for ((time_step, bbox), (expected_time_step, expected_bbox)) in
+            trajectory.into_iter().zip((1..).zip(&bboxes[1..]))
+        {
+            assert_eq!(time_step, expected_time_step);
+            assert_eq!(bbox, *expected_bbox);
+        }
+        assert!(oc_sort_tracker.trajectory(1).is_none());
+    }
+
+    #[test]
+    fn test_max_age_tentative_removes_one_frame_blip() {
+        let mut oc_sort_tracker = OCSort::from_config(OCSortConfig {
+            max_age_tentative: Some(1),
+            min_hit_streak: 2,
+            ..Default::default()
+        });
+        let object = Detection::new(BBox::new(0.0, 0.0, 10.0, 10.0), 1).with_score(0.9);
+        let blip = Detection::new(BBox::new(50.0, 0.0, 60.0, 10.0), 1).with_score(0.9);
+        oc_sort_tracker.update(std::slice::from_ref(&object));
+        oc_sort_tracker.update(&[object, blip]);
+        oc_sort_tracker.update(&[]);
+        oc_sort_tracker.update(&[]);
+
+        // The confirmed object survives the missed frames while the blip is removed.
+        let ids: Vec<u32> = oc_sort_tracker
+            .get_predicted_tracks()
+            .iter()
+            .map(|track| track.id)
+            .collect();
+        assert_eq!(ids, vec![0]);
+        assert_eq!(oc_sort_tracker.take_removed_ids(), vec![1]);
+    }
+
+    #[test]
+    fn test_update_with_debug_matrices_match_index_sets() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 1);
+        let detections = vec![
+            Detection::new(BBox::new(0.0, 0.0, 10.0, 10.0), 1).with_score(0.9),
+            Detection::new(BBox::new(20.0, 0.0, 30.0, 10.0), 1).with_score(0.9),
+        ];
+        let (_, debug) = oc_sort_tracker.update_with_debug(&detections);
+        assert!(debug.stages.is_empty());
+
+        let detections = vec![
+            detections[0].clone(),
+            Detection::new(BBox::new(50.0, 0.0, 60.0, 10.0), 1).with_score(0.9),
+            Detection::new(BBox::new(80.0, 0.0, 90.0, 10.0), 1).with_score(0.3),
+        ];
+        let (tracks, debug) = oc_sort_tracker.update_with_debug(&detections);
+
+        assert_eq!(tracks.len(), 2);
+        let stages: Vec<AssociationStage> = debug.stages.iter().map(|stage| stage.stage).collect();
+        assert_eq!(
+            stages,
+            vec![
+                AssociationStage::Primary,
+                AssociationStage::Byte,
+                AssociationStage::Ocr
+            ]
+        );
+        for stage in &debug.stages {
+            assert_eq!(stage.iou_matrix.len(), stage.detection_indices.len());
+            assert_eq!(stage.cost_matrix.len(), stage.detection_indices.len());
+            for (iou_row, cost_row) in stage.iou_matrix.iter().zip(&stage.cost_matrix) {
+                assert_eq!(iou_row.len(), stage.tracker_indices.len());
+                assert_eq!(cost_row.len(), stage.tracker_indices.len());
+            }
+        }
+        assert_eq!(debug.stages[0].detection_indices, vec![0, 1]);
+        assert_eq!(debug.stages[0].tracker_indices, vec![0, 1]);
+        assert!((debug.stages[0].iou_matrix[0][0] - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_update_matches_through_all_three_stages() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 1);
+        let detection = |x: f64, width: f64, score: f64| {
+            Detection::new(BBox::new(x, 0.0, x + width, 100.0), 1).with_score(score)
+        };
+
+        // Tracks 0 and 1 stay in place while track 2 moves fast to the right.
+        for i in 0..3 {
+            oc_sort_tracker.update(&[
+                detection(0.0, 100.0, 0.9),
+                detection(1000.0, 100.0, 0.9),
+                detection(2000.0 + 30.0 * i as f64, 100.0, 0.9),
+            ]);
+        }
+        // Track 2 is missed and coasts away from its last observation.
+        for _ in 0..3 {
+            oc_sort_tracker.update(&[detection(0.0, 100.0, 0.9), detection(1000.0, 100.0, 0.9)]);
+        }
+
+        // Track 0 is matched by the primary stage, track 1 by a low score detection in the
+        // BYTE stage and track 2 by its last observation in the OCR stage.
+        let (tracks, debug) = oc_sort_tracker.update_with_debug(&[
+            detection(0.0, 100.0, 0.9),
+            detection(1000.0, 100.0, 0.3),
+            detection(2060.0, 100.0, 0.9),
+        ]);
+        let stages: Vec<AssociationStage> = debug.stages.iter().map(|stage| stage.stage).collect();
+        assert_eq!(
+            stages,
+            vec![
+                AssociationStage::Primary,
+                AssociationStage::Byte,
+                AssociationStage::Ocr
+            ]
+        );
+        assert_eq!(debug.stages[0].tracker_indices, vec![0, 1, 2]);
+        assert_eq!(debug.stages[1].detection_indices, vec![1]);
+        assert_eq!(debug.stages[1].tracker_indices, vec![1, 2]);
+        assert_eq!(debug.stages[2].detection_indices, vec![2]);
+        assert_eq!(debug.stages[2].tracker_indices, vec![2]);
+        let mut ids: Vec<u32> = tracks.iter().map(|track| track.id).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, vec![0, 1, 2]);
+        assert_eq!(oc_sort_tracker.get_predicted_tracks().len(), 3);
+    }
+
+    #[test]
+    fn test_callbacks_fire_for_created_and_expired_track() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut oc_sort_tracker = OCSort::new(1, 0.3, 3, 0.5, 1);
+        let create_events = Arc::clone(&events);
+        oc_sort_tracker.on_create(move |id, track| {
+            create_events
+                .lock()
+                .unwrap()
+                .push(format!("create {id} {}", track.class));
+        });
+        let delete_events = Arc::clone(&events);
+        oc_sort_tracker.on_delete(move |id| {
+            delete_events.lock().unwrap().push(format!("delete {id}"));
+        });
+
+        oc_sort_tracker
+            .update(&[Detection::new(BBox::new(0.0, 0.0, 10.0, 10.0), 1).with_score(0.9)]);
+        for _ in 0..3 {
+            oc_sort_tracker.update(&[]);
+        }
+
+        assert_eq!(*events.lock().unwrap(), vec!["create 0 1", "delete 0"]);
+    }
+
+    #[test]
+    fn test_update_dt_matches_repeated_unit_steps() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 1);
+        for i in 0..6 {
+            let x = i as f64 * 2.0;
+            oc_sort_tracker
+                .update(&[Detection::new(BBox::new(x, 0.0, x + 10.0, 10.0), 1).with_score(0.9)]);
+        }
+        let mut unit_step_tracker = OCSort::load_state(oc_sort_tracker.save_state());
+
+        oc_sort_tracker.update_dt(&[], 2.0);
+        unit_step_tracker.update(&[]);
+        unit_step_tracker.update(&[]);
+
+        let bbox = oc_sort_tracker.get_predicted_tracks()[0].bbox;
+        let unit_step_bbox = unit_step_tracker.get_predicted_tracks()[0].bbox;
+        let tolerance = 1e-9;
+        assert!((bbox.x_1 - unit_step_bbox.x_1).abs() < tolerance);
+        assert!((bbox.x_2 - unit_step_bbox.x_2).abs() < tolerance);
+        // The object moves 2 per time step and was last seen at x_1 = 10.
+        assert!((bbox.x_1 - 14.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_update_dt_re_associates_after_gap_with_uneven_intervals() {
+        let mut oc_sort_tracker = OCSort::new(10, 0.3, 3, 0.5, 1);
+        // The object moves 1 per time step and is detected every half time step.
+        let mut tracks = Vec::new();
+        for i in 0..20 {
+            tracks = oc_sort_tracker.update_dt(&[detection_at(i as f64 * 0.5, 1)], 0.5);
+        }
+        let id = tracks[0].id;
+
+        // Missed for two time steps, then found again after a stall of three time steps.
+        for _ in 0..4 {
+            oc_sort_tracker.update_dt(&[], 0.5);
+        }
+        let tracks = oc_sort_tracker.update_dt(&[detection_at(14.5, 1)], 3.0);
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].id, id);
+
+        // The re-update keeps the speed of 1 per time step.
+        let bbox = oc_sort_tracker.predict_only()[0].bbox;
+        assert!((bbox.x_1 - 15.5).abs() < 0.2, "{bbox:?}");
+    }
+
+    #[test]
+    #[should_panic(expected = "dt must be positive and finite")]
+    fn test_update_dt_rejects_non_positive_dt() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 1);
+        oc_sort_tracker.update(&[Detection::new(BBox::new(0.0, 0.0, 1.0, 1.0), 1)]);
+
+        oc_sort_tracker.update_dt(&[], 0.0);
+    }
+
+    #[test]
+    fn test_from_config_rejects_invalid_cost_multiplier() {
+        for cost_multiplier in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let result = std::panic::catch_unwind(|| {
+                OCSort::from_config(OCSortConfig {
+                    cost_multiplier,
+                    ..Default::default()
+                })
+            });
+            assert!(
+                result.is_err(),
+                "cost_multiplier {cost_multiplier} was accepted"
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_config_rejects_velocity_damping_outside_unit_interval() {
+        for velocity_damping in [-0.1, 1.5, f64::NAN] {
+            let result = std::panic::catch_unwind(|| {
+                OCSort::from_config(OCSortConfig {
+                    velocity_damping,
+                    ..Default::default()
+                })
+            });
+            assert!(
+                result.is_err(),
+                "velocity_damping {velocity_damping} was accepted"
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_config_rejects_score_decay_outside_unit_interval() {
+        for score_decay in [-0.5, 1.1, f64::NAN] {
+            let result = std::panic::catch_unwind(|| {
+                OCSort::from_config(OCSortConfig {
+                    score_decay,
+                    ..Default::default()
+                })
+            });
+            assert!(result.is_err(), "score_decay {score_decay} was accepted");
+        }
+    }
+
+    #[test]
+    fn test_from_config_rejects_invalid_max_age_seconds_and_fps() {
+        let invalid = [
+            (Some(1.0), None),
+            (None, Some(30.0)),
+            (Some(0.0), Some(30.0)),
+            (Some(-1.0), Some(30.0)),
+            (Some(f64::NAN), Some(30.0)),
+            (Some(1.0), Some(0.0)),
+            (Some(1.0), Some(f64::INFINITY)),
+            (Some(1e9), Some(1e3)),
+        ];
+        for (max_age_seconds, fps) in invalid {
+            let result = std::panic::catch_unwind(|| {
+                OCSort::from_config(OCSortConfig {
+                    max_age_seconds,
+                    fps,
+                    ..Default::default()
+                })
+            });
+            assert!(
+                result.is_err(),
+                "max_age_seconds {max_age_seconds:?} with fps {fps:?} was accepted"
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "cost_multiplier must be positive and finite")]
+    fn test_load_state_rejects_invalid_cost_multiplier() {
+        let mut state = OCSort::new(5, 0.3, 3, 0.5, 1).save_state();
+        state.config.cost_multiplier = 0.0;
+
+        OCSort::load_state(state);
+    }
+
+    #[test]
+    fn test_take_removed_ids_reports_expired_track_once() {
+        let mut oc_sort_tracker = OCSort::new(1, 0.3, 3, 0.5, 1);
+        let detections = vec![Detection::new(BBox::new(0.0, 0.0, 1.0, 1.0), 1).with_score(0.7)];
+        let tracks = oc_sort_tracker.update(&detections);
+        let id = tracks[0].id;
+
+        oc_sort_tracker.update(&Vec::new());
+        assert!(oc_sort_tracker.take_removed_ids().is_empty());
+
+        oc_sort_tracker.update(&Vec::new());
+        assert_eq!(oc_sort_tracker.take_removed_ids(), vec![id]);
+
+        oc_sort_tracker.update(&Vec::new());
+        assert!(oc_sort_tracker.take_removed_ids().is_empty());
+    }
+
+    #[test]
+    fn test_reset_clears_tracked_objects() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 1);
+        for x in [0.0, 0.5, 1.0] {
+            let detections =
+                vec![Detection::new(BBox::new(x, 0.0, x + 1.0, 1.0), 1).with_score(0.7)];
+            oc_sort_tracker.update(&detections);
+        }
+        assert_eq!(oc_sort_tracker.trackers[0].hit_streak, 3);
+
+        oc_sort_tracker.reset();
+        assert!(oc_sort_tracker.get_trackers().is_empty());
+
+        let detections = vec![Detection::new(BBox::new(5.0, 5.0, 6.0, 6.0), 1).with_score(0.7)];
+        let tracks = oc_sort_tracker.update(&detections);
+
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].id, 0);
+        assert_eq!(tracks[0].class, 1);
+        assert_eq!(oc_sort_tracker.trackers.len(), 1);
+        assert_eq!(oc_sort_tracker.trackers[0].hit_streak, 1);
+    }
+
+    #[test]
+    fn test_instances_hand_out_ids_independently() {
+        let detections = vec![Detection::new(BBox::new(0.0, 0.0, 1.0, 1.0), 1).with_score(0.7)];
+        let mut oc_sort_tracker_1 = OCSort::new(5, 0.3, 3, 0.5, 1);
+        let mut oc_sort_tracker_2 = OCSort::new(5, 0.3, 3, 0.5, 1);
+
+        let tracks_1 = oc_sort_tracker_1.update(&detections);
+        let tracks_2 = oc_sort_tracker_2.update(&detections);
+
+        assert_eq!(tracks_1[0].id, 0);
+        assert_eq!(tracks_2[0].id, 0);
+    }
+
+    #[test]
+    fn test_last_u32_id_is_handed_out() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 1);
+        oc_sort_tracker.next_id = Some(u32::MAX);
+        let detections = vec![Detection::new(BBox::new(0.0, 0.0, 1.0, 1.0), 1).with_score(0.7)];
+
+        let tracks = oc_sort_tracker.update(&detections);
+
+        assert_eq!(tracks[0].id, u32::MAX);
+        assert_eq!(oc_sort_tracker.next_id, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "track ids exhausted")]
+    fn test_update_panics_when_ids_are_exhausted() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 1);
+        oc_sort_tracker.next_id = Some(u32::MAX);
+        let detections = vec![
+            Detection::new(BBox::new(0.0, 0.0, 1.0, 1.0), 1).with_score(0.7),
+            Detection::new(BBox::new(5.0, 5.0, 6.0, 6.0), 1).with_score(0.7),
+        ];
+
+        oc_sort_tracker.update(&detections);
+    }
+
+    #[test]
+    fn test_reset_restores_exhausted_ids() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 1);
+        oc_sort_tracker.next_id = None;
+
+        oc_sort_tracker.reset();
+
+        assert_eq!(oc_sort_tracker.next_id, Some(0));
+    }
+
+    #[test]
+    fn test_load_state_continues_identically() {
+        let frames: Vec<Vec<Detection>> = (0..5)
+            .map(|i| {
+                let x = i as f64 * 0.5;
+                vec![
+                    Detection::new(BBox::new(x, 0.0, x + 1.0, 1.0), 1).with_score(0.7),
+                    Detection::new(BBox::new(10.0 - x, 5.0, 11.0 - x, 6.0), 2).with_score(0.4),
+                ]
+            })
+            .collect();
+
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 1);
+        for detections in &frames[..3] {
+            oc_sort_tracker.update(detections);
+        }
+        let mut restored_tracker = OCSort::load_state(oc_sort_tracker.save_state());
+
+        for detections in &frames[3..] {
+            let tracks = oc_sort_tracker.update(detections);
+            let restored_tracks = restored_tracker.update(detections);
+
+            assert_eq!(tracks.len(), restored_tracks.len());
+            for (track, restored_track) in tracks.iter().zip(restored_tracks.iter()) {
+                assert_eq!(track.id, restored_track.id);
+                assert_eq!(track.class, restored_track.class);
+                assert_eq!(track.bbox.x_1, restored_track.bbox.x_1);
+                assert_eq!(track.bbox.y_1, restored_track.bbox.y_1);
+                assert_eq!(track.bbox.x_2, restored_track.bbox.x_2);
+                assert_eq!(track.bbox.y_2, restored_track.bbox.y_2);
+            }
+        }
+    }
+
+    #[test]
+    fn test_frame_size_clamps_returned_tracks() {
+        let mut oc_sort_tracker = OCSort::from_config(OCSortConfig {
+            min_hit_streak: 1,
+            frame_size: Some((100.0, 100.0)),
+            ..Default::default()
+        });
+        let detections =
+            vec![Detection::new(BBox::new(90.0, -5.0, 110.0, 15.0), 1).with_score(0.7)];
+
+        let tracks = oc_sort_tracker.update(&detections);
+
+        assert_eq!(tracks.len(), 1);
+        let tolerance = 1e-6;
+        assert!((tracks[0].bbox.x_1 - 90.0).abs() < tolerance);
+        assert_eq!(tracks[0].bbox.y_1, 0.0);
+        assert_eq!(tracks[0].bbox.x_2, 100.0);
+        assert!((tracks[0].bbox.y_2 - 15.0).abs() < tolerance);
+    }
+
+    #[test]
+    fn test_first_update_keeps_detection_class() {
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 1);
+        let detections = vec![Detection::new(BBox::new(0.0, 0.0, 1.0, 1.0), 7).with_score(0.7)];
+        let tracks = oc_sort_tracker.update(&detections);
+
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].class, 7);
+    }
+
+    #[test]
+    fn test_update_keeps_track_of_objects() {
+        let motorcycle_bboxes = [
+            BBox::new(187.0, 324.0, 303.0, 422.0),
+            BBox::new(183.0, 321.0, 302.0, 426.0),
+            BBox::new(180.0, 324.0, 303.0, 429.0),
+            BBox::new(179.0, 324.0, 303.0, 433.0),
+            BBox::new(168.0, 327.0, 305.0, 438.0),
+        ];
+
+        let person_bboxes = [
+            BBox::new(213.0, 280.0, 266.0, 402.0),
+            BBox::new(211.0, 278.0, 265.0, 403.0),
+            BBox::new(211.0, 278.0, 269.0, 406.0),
+            BBox::new(210.0, 276.0, 268.0, 405.0),
+            BBox::new(206.0, 277.0, 269.0, 408.0),
+        ];
+
+        let mut oc_sort_tracker = OCSort::new(5, 0.3, 3, 0.5, 1);
+
+        for i in 0..motorcycle_bboxes.len() {
+            let detections = vec![
+                Detection::new(motorcycle_bboxes[i], 3).with_score(0.9),
+                Detection::new(person_bboxes[i], 0).with_score(0.8),
             ];
             let tracks = oc_sort_tracker.update(&detections);
             assert_eq!(tracks.len(), 2);
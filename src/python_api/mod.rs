@@ -3,7 +3,7 @@ mod py_detection;
 mod py_oc_sort;
 mod py_track;
 
-pub use py_bbox::PyBBox;
+pub use py_bbox::{PyBBox, py_iou_batch};
 pub use py_detection::PyDetection;
 pub use py_oc_sort::PyOCSort;
 pub use py_track::PyTrack;
@@ -1,9 +1,14 @@
-use pyo3::{pyclass, pymethods};
+use std::hash::{DefaultHasher, Hash, Hasher};
 
-use crate::BBox;
+use pyo3::{
+    Bound, IntoPyObjectExt, PyObject, PyRef, PyResult, Python, pyclass, pyclass::CompareOp,
+    pyfunction, pymethods, types::PyType,
+};
+
+use crate::{BBox, iou_batch};
 
 #[pyclass(name = "BBox")]
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct PyBBox {
     pub inner: BBox,
 }
@@ -17,6 +22,73 @@ impl PyBBox {
         }
     }
 
+    #[classmethod]
+    fn from_cxcywh(_cls: &Bound<'_, PyType>, cx: f64, cy: f64, w: f64, h: f64) -> Self {
+        Self {
+            inner: BBox::from_cxcywh(cx, cy, w, h),
+        }
+    }
+
+    fn to_cxcywh(&self) -> (f64, f64, f64, f64) {
+        self.inner.to_cxcywh()
+    }
+
+    #[classmethod]
+    fn from_xywh(_cls: &Bound<'_, PyType>, x: f64, y: f64, w: f64, h: f64) -> Self {
+        Self {
+            inner: BBox::from_xywh(x, y, w, h),
+        }
+    }
+
+    fn to_xywh(&self) -> (f64, f64, f64, f64) {
+        self.inner.to_xywh()
+    }
+
+    #[classmethod]
+    fn from_normalized(
+        _cls: &Bound<'_, PyType>,
+        x_1: f64,
+        y_1: f64,
+        x_2: f64,
+        y_2: f64,
+        img_w: f64,
+        img_h: f64,
+    ) -> Self {
+        Self {
+            inner: BBox::from_normalized(x_1, y_1, x_2, y_2, img_w, img_h),
+        }
+    }
+
+    fn to_normalized(&self, img_w: f64, img_h: f64) -> Self {
+        Self {
+            inner: self.inner.to_normalized(img_w, img_h),
+        }
+    }
+
+    fn clamp(&self, width: f64, height: f64) -> Self {
+        Self {
+            inner: self.inner.clamp(width, height),
+        }
+    }
+
+    fn scale(&self, sx: f64, sy: f64) -> Self {
+        Self {
+            inner: self.inner.scale(sx, sy),
+        }
+    }
+
+    fn translate(&self, dx: f64, dy: f64) -> Self {
+        Self {
+            inner: self.inner.translate(dx, dy),
+        }
+    }
+
+    fn affine(&self, sx: f64, sy: f64, dx: f64, dy: f64) -> Self {
+        Self {
+            inner: self.inner.affine(sx, sy, dx, dy),
+        }
+    }
+
     #[getter]
     fn x_1(&self) -> f64 {
         self.inner.x_1
@@ -37,9 +109,24 @@ impl PyBBox {
         self.inner.y_2
     }
 
-    fn __repr__(&self) -> String {
+    #[getter]
+    fn center(&self) -> (f64, f64) {
+        self.inner.center()
+    }
+
+    #[getter]
+    fn width(&self) -> f64 {
+        self.inner.width()
+    }
+
+    #[getter]
+    fn height(&self) -> f64 {
+        self.inner.height()
+    }
+
+    pub fn __repr__(&self) -> String {
         format!(
-            "BBox(x_1={}, y_1={}, x_2={}, y_2={}",
+            "BBox(x_1={}, y_1={}, x_2={}, y_2={})",
             self.x_1(),
             self.y_1(),
             self.x_2(),
@@ -50,4 +137,29 @@ impl PyBBox {
     fn __str__(&self) -> String {
         self.__repr__()
     }
+
+    fn __richcmp__(&self, other: &Self, op: CompareOp, py: Python<'_>) -> PyResult<PyObject> {
+        match op {
+            CompareOp::Eq => (self.inner == other.inner).into_py_any(py),
+            CompareOp::Ne => (self.inner != other.inner).into_py_any(py),
+            _ => Ok(py.NotImplemented()),
+        }
+    }
+
+    fn __hash__(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for coordinate in [self.x_1(), self.y_1(), self.x_2(), self.y_2()] {
+            // Adding zero maps -0.0 to 0.0 so that equal boxes hash equally.
+            (coordinate + 0.0).to_bits().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+#[pyfunction(name = "iou_batch")]
+pub fn py_iou_batch(bboxes_1: Vec<PyRef<PyBBox>>, bboxes_2: Vec<PyRef<PyBBox>>) -> Vec<Vec<f64>> {
+    let to_inner = |bboxes: Vec<PyRef<PyBBox>>| -> Vec<BBox> {
+        bboxes.iter().map(|bbox| bbox.inner).collect()
+    };
+    iou_batch(&to_inner(bboxes_1), &to_inner(bboxes_2))
 }
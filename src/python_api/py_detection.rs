@@ -1,4 +1,4 @@
-use pyo3::{pyclass, pymethods};
+use pyo3::{IntoPyObjectExt, PyObject, PyResult, Python, pyclass, pyclass::CompareOp, pymethods};
 
 use crate::{Detection, python_api::PyBBox};
 
@@ -10,12 +10,21 @@ pub struct PyDetection {
 #[pymethods]
 impl PyDetection {
     #[new]
-    pub fn new(bbox: &PyBBox, class_id: u32, score: f64) -> Self {
+    #[pyo3(signature = (bbox, class_id, score, ext_id=None, embedding=None, uncertainty=None))]
+    pub fn new(
+        bbox: &PyBBox,
+        class_id: u32,
+        score: f64,
+        ext_id: Option<u64>,
+        embedding: Option<Vec<f32>>,
+        uncertainty: Option<[f64; 4]>,
+    ) -> Self {
         Self {
             inner: Detection {
-                bbox: bbox.inner,
-                class: class_id,
-                score: score,
+                ext_id,
+                embedding,
+                uncertainty,
+                ..Detection::new(bbox.inner, class_id).with_score(score)
             },
         }
     }
@@ -36,4 +45,39 @@ impl PyDetection {
     fn score(&self) -> f64 {
         self.inner.score
     }
+
+    #[getter]
+    fn ext_id(&self) -> Option<u64> {
+        self.inner.ext_id
+    }
+
+    #[getter]
+    fn embedding(&self) -> Option<Vec<f32>> {
+        self.inner.embedding.clone()
+    }
+
+    #[getter]
+    fn uncertainty(&self) -> Option<[f64; 4]> {
+        self.inner.uncertainty
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Detection(bbox={}, class_id={}, score={}, ext_id={})",
+            self.bbox().__repr__(),
+            self.inner.class,
+            self.inner.score,
+            self.inner
+                .ext_id
+                .map_or_else(|| "None".to_string(), |ext_id| ext_id.to_string())
+        )
+    }
+
+    fn __richcmp__(&self, other: &Self, op: CompareOp, py: Python<'_>) -> PyResult<PyObject> {
+        match op {
+            CompareOp::Eq => (self.inner == other.inner).into_py_any(py),
+            CompareOp::Ne => (self.inner != other.inner).into_py_any(py),
+            _ => Ok(py.NotImplemented()),
+        }
+    }
 }
@@ -1,10 +1,17 @@
-use pyo3::{PyRef, pyclass, pymethods};
+use std::collections::HashMap;
+
+use numpy::{IntoPyArray, PyArray2, PyReadonlyArray1, PyReadonlyArray2, ndarray::Array2};
+use pyo3::{Bound, PyRef, PyResult, Python, exceptions::PyValueError, pyclass, pymethods};
 
 use crate::{
-    Detection, OCSort,
-    python_api::{PyBBox, PyDetection, PyTrack},
+    BBox, ClassRegistry, Detection, OCSort,
+    python_api::{PyDetection, PyTrack},
 };
 
+// The number of columns in the track arrays returned by update_array:
+// (id, x_1, y_1, x_2, y_2, class, score).
+const TRACK_COLUMNS: usize = 7;
+
 #[pyclass(name = "OCSort")]
 pub struct PyOCSort {
     inner: OCSort,
@@ -35,28 +42,115 @@ impl PyOCSort {
         self.inner
             .get_trackers()
             .iter()
-            .map(|track| PyTrack {
-                id: track.id,
-                bbox: PyBBox { inner: track.bbox },
-                class_id: track.class,
-            })
+            .map(PyTrack::from)
             .collect()
     }
 
+    pub fn active_ids(&self) -> Vec<u32> {
+        self.inner.active_ids()
+    }
+
+    pub fn count_by_class(&self) -> HashMap<u32, usize> {
+        self.inner.count_by_class()
+    }
+
+    pub fn set_class_names(&mut self, names: HashMap<u32, String>) {
+        self.inner.set_class_registry(ClassRegistry::from(names));
+    }
+
+    pub fn class_name(&self, class_id: u32) -> Option<String> {
+        self.inner
+            .class_registry()
+            .and_then(|registry| registry.name(class_id))
+            .map(str::to_string)
+    }
+
+    pub fn reset(&mut self) {
+        self.inner.reset();
+    }
+
+    pub fn take_removed_ids(&mut self) -> Vec<u32> {
+        self.inner.take_removed_ids()
+    }
+
+    pub fn remove_track(&mut self, id: u32) -> bool {
+        self.inner.remove_track(id)
+    }
+
+    pub fn merge_tracks(&mut self, keep_id: u32, remove_id: u32) -> bool {
+        self.inner.merge_tracks(keep_id, remove_id)
+    }
+
     pub fn update(&mut self, detections: Vec<PyRef<PyDetection>>) -> Vec<PyTrack> {
         let inner_detections = detections
             .iter()
-            .map(|detection| detection.inner)
+            .map(|detection| detection.inner.clone())
             .collect::<Vec<Detection>>();
         let tracks = self.inner.update(&inner_detections);
 
-        tracks
+        tracks.iter().map(PyTrack::from).collect()
+    }
+
+    pub fn update_array<'py>(
+        &mut self,
+        py: Python<'py>,
+        boxes: PyReadonlyArray2<'py, f64>,
+        classes: PyReadonlyArray1<'py, i64>,
+        scores: PyReadonlyArray1<'py, f64>,
+    ) -> PyResult<Bound<'py, PyArray2<f64>>> {
+        let boxes = boxes.as_array();
+        let classes = classes.as_array();
+        let scores = scores.as_array();
+
+        if boxes.ncols() != 4 {
+            return Err(PyValueError::new_err(format!(
+                "boxes must have shape (N, 4), got {:?}",
+                boxes.shape()
+            )));
+        }
+        if classes.len() != boxes.nrows() || scores.len() != boxes.nrows() {
+            return Err(PyValueError::new_err(format!(
+                "classes and scores must have length {}, got {} and {}",
+                boxes.nrows(),
+                classes.len(),
+                scores.len()
+            )));
+        }
+
+        let inner_detections = boxes
+            .rows()
+            .into_iter()
+            .zip(classes)
+            .zip(scores)
+            .map(|((row, &class), &score)| {
+                let class = u32::try_from(class).map_err(|_| {
+                    PyValueError::new_err(format!("class ids must fit in u32, got {class}"))
+                })?;
+                Ok(
+                    Detection::new(BBox::new(row[0], row[1], row[2], row[3]), class)
+                        .with_score(score),
+                )
+            })
+            .collect::<PyResult<Vec<Detection>>>()?;
+        let tracks = self.inner.update(&inner_detections);
+
+        let data = tracks
             .iter()
-            .map(|track| PyTrack {
-                id: track.id,
-                bbox: PyBBox { inner: track.bbox },
-                class_id: track.class,
+            .flat_map(|track| {
+                [
+                    track.id as f64,
+                    track.bbox.x_1,
+                    track.bbox.y_1,
+                    track.bbox.x_2,
+                    track.bbox.y_2,
+                    track.class as f64,
+                    track.score,
+                ]
             })
-            .collect()
+            .collect();
+        let array = Array2::from_shape_vec((tracks.len(), TRACK_COLUMNS), data)
+            .expect("every track fills exactly one row");
+
+        Ok(array.into_pyarray(py))
     }
 }
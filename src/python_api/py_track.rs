@@ -1,14 +1,31 @@
-use pyo3::{pyclass, pymethods};
+use std::collections::HashMap;
 
-use crate::python_api::PyBBox;
+use pyo3::{IntoPyObjectExt, PyObject, PyResult, Python, pyclass, pyclass::CompareOp, pymethods};
+
+use crate::{Track, python_api::PyBBox};
 
 #[pyclass(name = "Track")]
+#[derive(PartialEq)]
 pub struct PyTrack {
     #[pyo3(get)]
     pub id: u32,
     pub bbox: PyBBox,
     #[pyo3(get)]
     pub class_id: u32,
+    #[pyo3(get)]
+    pub age: u32,
+    #[pyo3(get)]
+    pub time_since_update: u32,
+    #[pyo3(get)]
+    pub score: f64,
+    #[pyo3(get)]
+    pub uncertainty: (f64, f64),
+    #[pyo3(get)]
+    pub last_update_frame: u64,
+    #[pyo3(get)]
+    pub hit_streak: u32,
+    #[pyo3(get)]
+    pub confirmed: bool,
 }
 
 #[pymethods]
@@ -17,4 +34,45 @@ impl PyTrack {
     fn bbox(&self) -> PyBBox {
         self.bbox.clone()
     }
+
+    fn class_name(&self, names: HashMap<u32, String>) -> Option<String> {
+        names.get(&self.class_id).cloned()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Track(id={}, class_id={}, bbox={}, age={}, time_since_update={}, score={})",
+            self.id,
+            self.class_id,
+            self.bbox.__repr__(),
+            self.age,
+            self.time_since_update,
+            self.score
+        )
+    }
+
+    fn __richcmp__(&self, other: &Self, op: CompareOp, py: Python<'_>) -> PyResult<PyObject> {
+        match op {
+            CompareOp::Eq => (self == other).into_py_any(py),
+            CompareOp::Ne => (self != other).into_py_any(py),
+            _ => Ok(py.NotImplemented()),
+        }
+    }
+}
+
+impl From<&Track> for PyTrack {
+    fn from(track: &Track) -> Self {
+        Self {
+            id: track.id,
+            bbox: PyBBox { inner: track.bbox },
+            class_id: track.class,
+            age: track.age,
+            time_since_update: track.time_since_update,
+            score: track.score,
+            uncertainty: track.uncertainty,
+            last_update_frame: track.last_update_frame,
+            hit_streak: track.hit_streak,
+            confirmed: track.confirmed,
+        }
+    }
 }
@@ -0,0 +1,70 @@
+//! Counts the heap allocations of steady state updates with a counting global allocator.
+//! Lives in its own test binary so that other tests cannot allocate concurrently.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use oc_sort::{BBox, Detection, OCSort, OCSortConfig};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Creates a frame of high and low score detections moving right with the frame index.
+fn frame(frame_index: usize) -> Vec<Detection> {
+    let shift = frame_index as f64;
+    (0..20)
+        .map(|i| {
+            let x = i as f64 * 20.0 + shift;
+            Detection::new(BBox::new(x, 0.0, x + 10.0, 10.0), 0).with_score(if i % 4 == 0 {
+                0.4
+            } else {
+                0.9
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn test_steady_state_update_allocations() {
+    let frames: Vec<Vec<Detection>> = (0..20).map(frame).collect();
+    let mut oc_sort_tracker = OCSort::from_config(OCSortConfig::default());
+    for frame in &frames[..10] {
+        oc_sort_tracker.update(frame);
+    }
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for frame in &frames[10..] {
+        oc_sort_tracker.update(frame);
+    }
+    let allocations_per_frame = (ALLOCATIONS.load(Ordering::Relaxed) - before) / 10;
+
+    // 36 allocations per frame before the index and bounding box buffers were reused
+    // across updates, 28 after. The remaining ones are the stage results, the cost
+    // matrices, the solver and the returned tracks.
+    assert!(
+        allocations_per_frame <= 30,
+        "{allocations_per_frame} allocations per frame"
+    );
+}
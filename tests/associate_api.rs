@@ -0,0 +1,68 @@
+//! Builds a custom tracker loop out of the public association stages.
+
+use oc_sort::{
+    BBox, Detection, HungarianMatcher, KalmanBoxTracker, OCSortConfig,
+    associate::{associate_detections_to_trackers, byte_associate, observation_centric_recovery},
+};
+
+fn detection(x: f64, score: f64) -> Detection {
+    Detection::new(BBox::new(x, 0.0, x + 10.0, 10.0), 0).with_score(score)
+}
+
+#[test]
+fn test_stages_partition_detections_and_trackers() {
+    let config = OCSortConfig::default();
+    let mut trackers: Vec<KalmanBoxTracker> = [0.0, 50.0, 100.0]
+        .into_iter()
+        .enumerate()
+        .map(|(id, x)| KalmanBoxTracker::with_config(detection(x, 0.9).bbox, 0, id as u32, &config))
+        .collect();
+    trackers.iter_mut().for_each(|tracker| {
+        tracker.predict_dt(1.0);
+    });
+    assert!(
+        trackers
+            .iter()
+            .all(|tracker| tracker.time_since_update() == 1 && tracker.hit_streak() == 1)
+    );
+    let detections = [
+        detection(1.0, 0.9),
+        detection(51.0, 0.3),
+        detection(200.0, 0.9),
+    ];
+
+    let (matches, unmatched_detections, unmatched_trackers) = associate_detections_to_trackers(
+        &detections,
+        &[0, 2],
+        &trackers,
+        &[0, 1, 2],
+        &config,
+        &HungarianMatcher,
+    );
+    assert_eq!(matches, vec![(0, 0)]);
+    assert_eq!(unmatched_detections, vec![2]);
+    assert_eq!(unmatched_trackers, vec![1, 2]);
+
+    let (byte_matches, _, unmatched_trackers) = byte_associate(
+        &detections,
+        &[1],
+        &trackers,
+        &unmatched_trackers,
+        &config,
+        &HungarianMatcher,
+    );
+    assert_eq!(byte_matches, vec![(1, 1)]);
+    assert_eq!(unmatched_trackers, vec![2]);
+
+    let (ocr_matches, unmatched_detections, unmatched_trackers) = observation_centric_recovery(
+        &detections,
+        &unmatched_detections,
+        &trackers,
+        &unmatched_trackers,
+        &config,
+        &HungarianMatcher,
+    );
+    assert!(ocr_matches.is_empty());
+    assert_eq!(unmatched_detections, vec![2]);
+    assert_eq!(unmatched_trackers, vec![2]);
+}
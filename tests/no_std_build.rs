@@ -0,0 +1,38 @@
+//! Builds the crate without its default features: the no_std core, i.e. BBox and its
+//! geometry, and the Rust-only build without the Python bindings.
+
+use std::process::Command;
+
+/// Runs cargo build without the default features and with the given ones, and asserts that
+/// it succeeds.
+fn assert_builds_without_default_features(features: &[&str]) {
+    let mut command = Command::new(env!("CARGO"));
+    command.args([
+        "build",
+        "--no-default-features",
+        "--manifest-path",
+        concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"),
+        "--target-dir",
+        env!("CARGO_TARGET_TMPDIR"),
+    ]);
+    if !features.is_empty() {
+        command.args(["--features", &features.join(",")]);
+    }
+    let output = command.output().expect("failed to run cargo");
+
+    assert!(
+        output.status.success(),
+        "build with features {features:?} failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn test_no_std_core_builds() {
+    assert_builds_without_default_features(&["no_std"]);
+}
+
+#[test]
+fn test_builds_without_default_features() {
+    assert_builds_without_default_features(&[]);
+}
@@ -0,0 +1,42 @@
+from oc_sort import BBox, iou_batch
+
+
+def test_equal_bboxes_compare_and_hash_equal() -> None:
+    bbox_1 = BBox(0.0, 1.0, 2.0, 3.0)
+    bbox_2 = BBox(0.0, 1.0, 2.0, 3.0)
+
+    assert bbox_1 == bbox_2
+    assert hash(bbox_1) == hash(bbox_2)
+    assert len({bbox_1, bbox_2}) == 1
+    assert bbox_1 != BBox(0.0, 1.0, 2.0, 4.0)
+
+
+def test_repr_is_closed() -> None:
+    bbox = BBox(0.0, 1.0, 2.0, 3.0)
+
+    assert repr(bbox) == "BBox(x_1=0, y_1=1, x_2=2, y_2=3)"
+    assert repr(bbox).endswith(")")
+
+
+def test_center_and_dimensions() -> None:
+    bbox = BBox(2.0, 4.0, 8.0, 14.0)
+
+    assert bbox.center == (5.0, 9.0)
+    assert bbox.width == 6.0
+    assert bbox.height == 10.0
+
+
+def test_letterbox_round_trip() -> None:
+    bbox = BBox(100.0, 200.0, 300.0, 500.0)
+
+    letterboxed = bbox.affine(0.5, 0.5, 0.0, 140.0)
+
+    assert letterboxed == BBox(50.0, 240.0, 150.0, 390.0)
+    assert letterboxed.translate(0.0, -140.0).scale(2.0, 2.0) == bbox
+
+
+def test_iou_batch() -> None:
+    bboxes_1 = [BBox(0.0, 0.0, 2.0, 2.0), BBox(1.0, 1.0, 3.0, 3.0)]
+    bboxes_2 = [BBox(0.0, 0.0, 2.0, 2.0), BBox(2.0, 0.0, 4.0, 2.0)]
+
+    assert iou_batch(bboxes_1, bboxes_2) == [[1.0, 0.0], [1.0 / 7.0, 1.0 / 7.0]]
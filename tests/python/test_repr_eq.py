@@ -0,0 +1,23 @@
+from oc_sort import BBox, Detection, OCSort
+
+
+def test_detection_repr_and_equality() -> None:
+    detection = Detection(BBox(0.0, 0.0, 10.0, 10.0), 1, 0.5)
+
+    print(detection)
+    assert repr(detection) == (
+        "Detection(bbox=BBox(x_1=0, y_1=0, x_2=10, y_2=10), class_id=1, score=0.5, ext_id=None)"
+    )
+    assert detection == Detection(BBox(0.0, 0.0, 10.0, 10.0), 1, 0.5)
+    assert detection != Detection(BBox(0.0, 0.0, 10.0, 10.0), 1, 0.6)
+    assert detection != Detection(BBox(0.0, 0.0, 10.0, 10.0), 2, 0.5)
+
+
+def test_track_repr_and_equality() -> None:
+    detections = [Detection(BBox(0.0, 0.0, 10.0, 10.0), 1, 0.9)]
+    tracks_1 = OCSort(30, 0.3, 3, 0.5, 1).update(detections)
+    tracks_2 = OCSort(30, 0.3, 3, 0.5, 1).update(detections)
+
+    print(tracks_1[0])
+    assert repr(tracks_1[0]).startswith("Track(id=0, class_id=1, bbox=BBox(")
+    assert tracks_1[0] == tracks_2[0]
@@ -0,0 +1,27 @@
+import subprocess
+import sys
+from pathlib import Path
+
+ROOT = Path(__file__).resolve().parents[2]
+
+
+def test_stub_matches_extension_module() -> None:
+    result = subprocess.run(
+        [sys.executable, "-m", "mypy.stubtest", "oc_sort"],
+        cwd=ROOT,
+        capture_output=True,
+        text=True,
+    )
+
+    assert result.returncode == 0, result.stdout + result.stderr
+
+
+def test_python_tests_type_check_against_stub() -> None:
+    result = subprocess.run(
+        [sys.executable, "-m", "mypy", "--strict", str(ROOT / "tests" / "python")],
+        cwd=ROOT,
+        capture_output=True,
+        text=True,
+    )
+
+    assert result.returncode == 0, result.stdout + result.stderr
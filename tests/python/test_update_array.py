@@ -0,0 +1,42 @@
+import numpy as np
+import pytest
+
+from oc_sort import OCSort
+
+
+def test_update_array_returns_tracks() -> None:
+    tracker = OCSort(30, 0.3, 3, 0.5, 1)
+    boxes = np.array([[0.0, 0.0, 10.0, 10.0], [20.0, 0.0, 30.0, 10.0]])
+    classes = np.array([1, 2])
+    scores = np.array([0.9, 0.8])
+
+    tracks = tracker.update_array(boxes, classes, scores)
+
+    assert tracks.shape == (2, 7)
+    assert tracks.dtype == np.float64
+    assert tracks.flags["C_CONTIGUOUS"]
+    np.testing.assert_array_equal(tracks[:, 0], [0, 1])
+    np.testing.assert_allclose(tracks[:, 1:5], boxes)
+    np.testing.assert_array_equal(tracks[:, 5], classes)
+    np.testing.assert_allclose(tracks[:, 6], scores)
+
+
+def test_update_array_empty_frame_returns_empty_array() -> None:
+    tracker = OCSort(30, 0.3, 3, 0.5, 1)
+
+    tracks = tracker.update_array(
+        np.zeros((0, 4)), np.zeros(0, dtype=np.int64), np.zeros(0)
+    )
+
+    assert tracks.shape == (0, 7)
+    assert tracks.dtype == np.float64
+
+
+def test_update_array_rejects_mismatched_shapes() -> None:
+    tracker = OCSort(30, 0.3, 3, 0.5, 1)
+    boxes = np.array([[0.0, 0.0, 10.0, 10.0]])
+
+    with pytest.raises(ValueError):
+        tracker.update_array(boxes, np.array([1, 2]), np.array([0.9]))
+    with pytest.raises(ValueError):
+        tracker.update_array(np.zeros((1, 3)), np.array([1]), np.array([0.9]))
@@ -0,0 +1,22 @@
+//! Exercises the public Rust API. Runs without the `python` feature by default,
+//! confirming that the crate builds and works without pyo3.
+
+use oc_sort::{BBox, Detection, OCSort, OCSortConfig};
+
+#[test]
+fn test_tracks_object_without_python_feature() {
+    let mut oc_sort_tracker = OCSort::from_config(OCSortConfig {
+        min_hit_streak: 1,
+        ..Default::default()
+    });
+
+    let mut tracks = Vec::new();
+    for i in 0..3 {
+        let x = i as f64;
+        tracks = oc_sort_tracker
+            .update(&[Detection::new(BBox::new(x, 0.0, x + 10.0, 10.0), 0).with_score(0.9)]);
+    }
+
+    assert_eq!(tracks.len(), 1);
+    assert_eq!(tracks[0].id, 0);
+}